@@ -0,0 +1,119 @@
+use anyhow::{anyhow, Context, Result};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use gstreamer_app as gst_app;
+use gstreamer_audio as gst_audio;
+use log::debug;
+use std::sync::{Arc, Mutex};
+
+/// Encoding takes a fraction of real time; a pipeline that has neither
+/// finished nor failed by then is stuck.
+const ENCODE_TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(120);
+
+/// Runs `f32` samples through a GStreamer encoder such as `flacenc` and returns
+/// the encoded stream. Blocks until the whole recording has been encoded.
+pub fn encode_with_gstreamer(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    encoder: &str,
+) -> Result<Vec<u8>> {
+    debug!(
+        "Encoding {} samples with '{}' ({} Hz, {} channel(s))",
+        samples.len(),
+        encoder,
+        sample_rate,
+        channels
+    );
+
+    let pipeline_desc = format!(
+        "appsrc name=src format=time ! audioconvert ! audioresample ! {} ! appsink name=sink sync=false",
+        encoder
+    );
+    let pipeline = gst::parse_launch(&pipeline_desc)
+        .context("Failed to create GStreamer encoding pipeline")?;
+    let pipeline = pipeline.downcast::<gst::Pipeline>().unwrap();
+
+    let src = pipeline
+        .by_name("src")
+        .context("Source element not found")?
+        .downcast::<gst_app::AppSrc>()
+        .map_err(|_| anyhow!("Source element is not an AppSrc"))?;
+    let sink = pipeline
+        .by_name("sink")
+        .context("Sink element not found")?
+        .downcast::<gst_app::AppSink>()
+        .map_err(|_| anyhow!("Sink element is not an AppSink"))?;
+
+    // The encoder emits its stream headers as the first buffers, so the
+    // concatenated output is a complete file
+    let encoded = Arc::new(Mutex::new(Vec::new()));
+    let output = Arc::clone(&encoded);
+    sink.set_callbacks(
+        gst_app::AppSinkCallbacks::builder()
+            .new_sample(move |sink| {
+                let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                if let Some(buffer) = sample.buffer() {
+                    let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+                    output.lock().unwrap().extend_from_slice(&map);
+                }
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    src.set_caps(Some(
+        &gst_audio::AudioInfo::builder(gst_audio::AudioFormat::F32le, sample_rate, channels as u32)
+            .build()
+            .context("Failed to build AudioInfo")?
+            .to_caps()
+            .context("Failed to convert AudioInfo to caps")?,
+    ));
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .context("Failed to set encoding pipeline to Playing state")?;
+
+    let frames = samples.len() as u64 / channels as u64;
+    let byte_data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    let mut buffer = gst::Buffer::from_mut_slice(byte_data);
+    {
+        let buffer_ref = buffer.get_mut().unwrap();
+        buffer_ref.set_pts(gst::ClockTime::ZERO);
+        buffer_ref.set_duration(gst::ClockTime::from_nseconds(
+            frames * 1_000_000_000 / sample_rate as u64,
+        ));
+    }
+    src.push_buffer(buffer)
+        .context("Failed to push buffer to source")?;
+    src.end_of_stream()
+        .context("Failed to signal end of stream")?;
+
+    // Errors anywhere in the pipeline end up on the bus, not at the appsink
+    let message = pipeline
+        .bus()
+        .context("Failed to get encoding pipeline bus")?
+        .timed_pop_filtered(
+            ENCODE_TIMEOUT,
+            &[gst::MessageType::Eos, gst::MessageType::Error],
+        );
+    pipeline
+        .set_state(gst::State::Null)
+        .context("Failed to set encoding pipeline to Null state")?;
+    match message.as_ref().map(|message| message.view()) {
+        Some(gst::MessageView::Eos(_)) => {}
+        Some(gst::MessageView::Error(err)) => {
+            return Err(anyhow!("GStreamer encoding failed: {}", err.error()));
+        }
+        _ => {
+            return Err(anyhow!(
+                "GStreamer encoding did not finish within {} seconds",
+                ENCODE_TIMEOUT.seconds()
+            ));
+        }
+    }
+
+    let encoded = std::mem::take(&mut *encoded.lock().unwrap());
+    debug!("Encoded audio to {} bytes", encoded.len());
+    Ok(encoded)
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use log::{debug, info, warn};
+
+/// Device properties that identify a source across restarts, most specific first.
+const DEVICE_ID_PROPERTIES: &[&str] = &["node.name", "device.name", "object.path", "udev.id"];
+
+/// A microphone or other audio source found by GStreamer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputDevice {
+    /// Stable identifier stored in the configuration
+    pub id: String,
+    /// Name shown in the device picker
+    pub name: String,
+}
+
+/// Lists the audio sources currently available.
+pub fn list_input_devices() -> Result<Vec<InputDevice>> {
+    Ok(probe_sources()?
+        .iter()
+        .map(|device| InputDevice {
+            id: device_id(device),
+            name: device.display_name().to_string(),
+        })
+        .collect())
+}
+
+/// Creates the source element for the configured device, or `autoaudiosrc`
+/// when no device is configured or it is no longer available.
+pub fn create_source(device_id: Option<&str>) -> Result<gst::Element> {
+    if let Some(device_id) = device_id {
+        match find_source(device_id) {
+            Ok(Some(source)) => {
+                info!("Recording from input device {}", device_id);
+                return Ok(source);
+            }
+            Ok(None) => warn!(
+                "Input device {} not found, recording from the default device",
+                device_id
+            ),
+            Err(e) => warn!(
+                "Could not open input device {}, recording from the default device: {:#}",
+                device_id, e
+            ),
+        }
+    }
+    gst::ElementFactory::make("autoaudiosrc")
+        .build()
+        .context("Failed to create autoaudiosrc")
+}
+
+fn find_source(device_id: &str) -> Result<Option<gst::Element>> {
+    let Some(device) = probe_sources()?
+        .into_iter()
+        .find(|device| device_id(device) == device_id)
+    else {
+        return Ok(None);
+    };
+    let source = device
+        .create_element(None)
+        .with_context(|| format!("Failed to create source for {}", device.display_name()))?;
+    Ok(Some(source))
+}
+
+fn probe_sources() -> Result<Vec<gst::Device>> {
+    let monitor = gst::DeviceMonitor::new();
+    if monitor.add_filter(Some("Audio/Source"), None).is_none() {
+        warn!("Could not filter devices for audio sources");
+    }
+    monitor
+        .start()
+        .context("Failed to start GStreamer device monitor")?;
+    let devices: Vec<gst::Device> = monitor.devices().into_iter().collect();
+    monitor.stop();
+    debug!("Found {} audio source(s)", devices.len());
+    Ok(devices)
+}
+
+fn device_id(device: &gst::Device) -> String {
+    device
+        .properties()
+        .and_then(|properties| {
+            DEVICE_ID_PROPERTIES
+                .iter()
+                .find_map(|key| properties.get::<String>(*key).ok())
+        })
+        .unwrap_or_else(|| device.display_name().to_string())
+}
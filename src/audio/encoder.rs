@@ -0,0 +1,80 @@
+use super::compress::encode_with_gstreamer;
+use anyhow::Result;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// Container and codec of the audio file uploaded to the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadCodec {
+    /// Uncompressed 16-bit PCM
+    Wav,
+    /// Lossless, usually about half the size of WAV
+    #[default]
+    Flac,
+    /// Ogg/Opus at speech bitrate, a small fraction of the WAV size
+    Opus,
+}
+
+impl UploadCodec {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            UploadCodec::Wav => "audio/wav",
+            UploadCodec::Flac => "audio/flac",
+            UploadCodec::Opus => "audio/ogg",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            UploadCodec::Wav => "audio.wav",
+            UploadCodec::Flac => "audio.flac",
+            UploadCodec::Opus => "audio.ogg",
+        }
+    }
+}
+
+/// Encodes mono or interleaved `f32` samples into an upload file of the given codec.
+pub fn encode_audio(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    codec: UploadCodec,
+) -> Result<Vec<u8>> {
+    match codec {
+        UploadCodec::Wav => encode_wav(samples, sample_rate, channels),
+        UploadCodec::Flac => encode_with_gstreamer(samples, sample_rate, channels, "flacenc"),
+        UploadCodec::Opus => encode_with_gstreamer(
+            samples,
+            sample_rate,
+            channels,
+            "opusenc bitrate=32000 ! oggmux",
+        ),
+    }
+}
+
+/// Encodes mono or interleaved `f32` samples as a 16-bit PCM WAV file in memory.
+pub fn encode_wav(samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>> {
+    debug!(
+        "Encoding {} samples as WAV ({} Hz, {} channel(s))",
+        samples.len(),
+        sample_rate,
+        channels
+    );
+
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav_buffer = Cursor::new(Vec::new());
+    let mut wav_writer = hound::WavWriter::new(&mut wav_buffer, spec)?;
+    for &sample in samples {
+        wav_writer.write_sample((sample.clamp(-1.0, 1.0) * 32767.0) as i16)?;
+    }
+    wav_writer.finalize()?;
+
+    Ok(wav_buffer.into_inner())
+}
@@ -0,0 +1,67 @@
+/// How much audio one level reading covers, for about 20 updates per second.
+const LEVEL_INTERVAL_SECS: f64 = 0.05;
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.99;
+
+/// Input level over a short stretch of the recording, as linear amplitudes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InputLevel {
+    pub rms: f32,
+    pub peak: f32,
+    pub clipped: bool,
+}
+
+impl InputLevel {
+    /// `amplitude` in decibels relative to full scale, floored at `min_db`.
+    pub fn to_dbfs(amplitude: f32, min_db: f32) -> f32 {
+        if amplitude <= 0.0 {
+            return min_db;
+        }
+        (20.0 * amplitude.log10()).max(min_db)
+    }
+}
+
+/// Turns the recorded sample buffers into level readings at a steady rate.
+#[derive(Debug)]
+pub struct LevelMeter {
+    samples_per_reading: usize,
+    sum_squares: f64,
+    peak: f32,
+    samples: usize,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let frames = (sample_rate as f64 * LEVEL_INTERVAL_SECS) as usize;
+        Self {
+            samples_per_reading: (frames * channels.max(1) as usize).max(1),
+            sum_squares: 0.0,
+            peak: 0.0,
+            samples: 0,
+        }
+    }
+
+    /// Adds a buffer of interleaved samples and returns a reading once enough
+    /// audio has been seen. Buffers are not split, so a reading may cover a
+    /// little more than the interval.
+    pub fn push(&mut self, samples: &[f32]) -> Option<InputLevel> {
+        for &sample in samples {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.samples += samples.len();
+        if self.samples < self.samples_per_reading {
+            return None;
+        }
+
+        let level = InputLevel {
+            rms: (self.sum_squares / self.samples as f64).sqrt() as f32,
+            peak: self.peak,
+            clipped: self.peak >= CLIP_LEVEL,
+        };
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.samples = 0;
+        Some(level)
+    }
+}
@@ -1,5 +1,14 @@
+mod compress;
+mod devices;
+mod encoder;
+mod level;
 mod recorder;
+mod resample;
 mod player;
 
+pub use devices::{create_source, list_input_devices, InputDevice};
+pub use encoder::{encode_audio, UploadCodec};
+pub use level::{InputLevel, LevelMeter};
 pub use recorder::record_audio;
+pub use resample::{downmix_to_mono, resample};
 pub use player::play_audio;
\ No newline at end of file
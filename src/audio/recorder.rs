@@ -1,21 +1,27 @@
+use super::{create_source, LevelMeter};
 use crate::state::{AppStateEnum, StateManager};
 use anyhow::{anyhow, Context as _};
 use gstreamer as gst;
 use gstreamer::prelude::*;
 use gstreamer_app as gst_app;
 use log::{debug, info, trace};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::select;
+use tokio::sync::broadcast;
 use tokio::time::{Duration, Instant};
 
-pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()> {
+/// Records until `stop_receiver` fires or the app leaves the recording state.
+/// `StateManager::start_recording` must have been called for this recording.
+pub async fn record_audio(
+    state_manager: Arc<StateManager>,
+    mut stop_receiver: broadcast::Receiver<()>,
+) -> anyhow::Result<()> {
     info!("Starting audio recording");
     let config = Arc::clone(&state_manager).get_config();
-    let max_duration = Duration::from_secs_f64(config.max_recording_duration());
-    debug!("Max recording duration: {:?}", max_duration);
 
+    // The source is added separately, since it depends on the chosen device
     let pipeline_desc = format!(
-        "autoaudiosrc ! audioconvert ! audioresample ! audio/x-raw,rate={},channels={},format=F32LE ! appsink name=sink",
+        "audioconvert name=convert ! audioresample ! audio/x-raw,rate={},channels={},format=F32LE ! appsink name=sink",
         config.sample_rate,
         config.channels
     );
@@ -23,6 +29,17 @@ pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()
         .map_err(|e| anyhow!("Failed to create GStreamer pipeline: {}", e))?;
     let pipeline = pipeline.downcast::<gst::Pipeline>().unwrap();
 
+    let source = create_source(config.input_device.as_deref())?;
+    let convert = pipeline
+        .by_name("convert")
+        .ok_or_else(|| anyhow!("Convert element not found"))?;
+    pipeline
+        .add(&source)
+        .context("Failed to add the audio source to the pipeline")?;
+    source
+        .link(&convert)
+        .context("Failed to link the audio source")?;
+
     debug!("GStreamer pipeline created");
 
     let sink = pipeline
@@ -33,6 +50,7 @@ pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()
     debug!("AppSink element retrieved from pipeline");
 
     let state_manager_clone = Arc::clone(&state_manager);
+    let level_meter = Mutex::new(LevelMeter::new(config.sample_rate, config.channels));
     sink.set_callbacks(
         gst_app::AppSinkCallbacks::builder()
             .new_sample(move |sink| {
@@ -45,6 +63,9 @@ pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()
                 let new_data = bytemuck::cast_slice::<u8, f32>(&map).to_vec();
 
                 state_manager_clone.append_audio_data(&new_data);
+                if let Some(level) = level_meter.lock().unwrap().push(&new_data) {
+                    state_manager_clone.publish_level(level);
+                }
 
                 if state_manager_clone.get_app_state() == AppStateEnum::Recording {
                     Ok(gst::FlowSuccess::Ok)
@@ -61,8 +82,6 @@ pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()
         .map_err(|e| anyhow::anyhow!("Failed to set pipeline to Playing state: {:?}", e))?;
     info!("GStreamer pipeline started");
 
-    let mut stop_receiver = Arc::clone(&state_manager).start_recording();
-
     let start_time = Instant::now();
     let state_manager_clone = Arc::clone(&state_manager);
     tokio::spawn(async move {
@@ -77,11 +96,6 @@ pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()
                         info!("Recording stopped");
                         break;
                     }
-                    if start_time.elapsed() >= max_duration {
-                        info!("Maximum recording duration reached");
-                        state_manager_clone.set_app_state(AppStateEnum::Recorded);
-                        break;
-                    }
                     debug!("Recording in progress: {:?} elapsed", start_time.elapsed());
                 }
             }
@@ -96,6 +110,7 @@ pub async fn record_audio(state_manager: Arc<StateManager>) -> anyhow::Result<()
     pipeline
         .set_state(gst::State::Null)
         .map_err(|e| anyhow::anyhow!("Failed to set pipeline to Null state: {:?}", e))?;
+    state_manager.publish_level(Default::default());
     info!("Audio recording completed");
 
     Ok(())
@@ -0,0 +1,178 @@
+use log::debug;
+
+/// Averages interleaved channels down to a single mono channel.
+pub fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Zero crossings on each side of the sinc kernel; more gives a steeper low-pass.
+const SINC_ZERO_CROSSINGS: f64 = 16.0;
+/// Upper bound on precomputed filter phases. Common rate pairs need far fewer
+/// (160 for 44.1 kHz to 16 kHz); unusual ones round to the nearest phase.
+const MAX_PHASES: u64 = 1024;
+
+/// Resamples mono audio from `from_rate` to `to_rate` with a Blackman-windowed sinc
+/// filter. When downsampling, frequencies above the new Nyquist limit are filtered
+/// out instead of aliasing into the speech band.
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    debug!(
+        "Resampling {} samples from {} Hz to {} Hz",
+        samples.len(),
+        from_rate,
+        to_rate
+    );
+
+    // Output sample i lies i * down / up input samples into the recording
+    let divisor = gcd(from_rate as u64, to_rate as u64);
+    let (up, down) = (to_rate as u64 / divisor, from_rate as u64 / divisor);
+    let ratio = from_rate as f64 / to_rate as f64;
+    // Cutoff in cycles per input sample, slightly below the lower of the two Nyquist limits
+    let cutoff = 0.5 * 0.95 * (1.0 / ratio).min(1.0);
+    let half_width = SINC_ZERO_CROSSINGS / (2.0 * cutoff);
+    let filter = PolyphaseFilter::new(up.min(MAX_PHASES), cutoff, half_width);
+
+    let output_len = (samples.len() as f64 / ratio).floor() as usize;
+    (0..output_len as u64)
+        .map(|i| {
+            let (whole, remainder) = (i * down / up, i * down % up);
+            let phase = (remainder * filter.phases + up / 2) / up;
+            let (centre, phase) = if phase == filter.phases {
+                (whole + 1, 0)
+            } else {
+                (whole, phase)
+            };
+            filter.apply(samples, centre as usize, phase as usize)
+        })
+        .collect()
+}
+
+/// Kernel taps for each fractional position between two input samples, so
+/// resampling is a dot product instead of trigonometry per tap.
+struct PolyphaseFilter {
+    phases: u64,
+    /// Input samples used on each side of the centre
+    reach: usize,
+    /// `phases` rows of `2 * reach + 1` taps
+    taps: Vec<f32>,
+}
+
+impl PolyphaseFilter {
+    fn new(phases: u64, cutoff: f64, half_width: f64) -> Self {
+        let reach = half_width.ceil() as usize;
+        let width = 2 * reach + 1;
+        let mut taps = Vec::with_capacity(phases as usize * width);
+        for phase in 0..phases {
+            let fraction = phase as f64 / phases as f64;
+            taps.extend((0..width).map(|tap| {
+                let offset = fraction + reach as f64 - tap as f64;
+                if offset.abs() <= half_width {
+                    sinc_kernel(offset, cutoff, half_width) as f32
+                } else {
+                    0.0
+                }
+            }));
+        }
+        Self {
+            phases,
+            reach,
+            taps,
+        }
+    }
+
+    /// Filtered value at `phase` / `phases` samples past `samples[centre]`.
+    fn apply(&self, samples: &[f32], centre: usize, phase: usize) -> f32 {
+        let width = 2 * self.reach + 1;
+        let taps = &self.taps[phase * width..(phase + 1) * width];
+        let first = centre.saturating_sub(self.reach);
+        let last = (centre + self.reach).min(samples.len() - 1);
+        if first > last {
+            return 0.0;
+        }
+        let skipped = first + self.reach - centre;
+        samples[first..=last]
+            .iter()
+            .zip(&taps[skipped..])
+            .map(|(sample, tap)| sample * tap)
+            .sum()
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Windowed low-pass kernel evaluated `offset` input samples from its centre.
+fn sinc_kernel(offset: f64, cutoff: f64, half_width: f64) -> f64 {
+    use std::f64::consts::PI;
+
+    let x = 2.0 * cutoff * offset;
+    let sinc = if x.abs() < 1e-9 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    };
+    let w = offset / half_width;
+    let window = 0.42 + 0.5 * (PI * w).cos() + 0.08 * (2.0 * PI * w).cos();
+    2.0 * cutoff * sinc * window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency: f64, rate: u32, secs: f64) -> Vec<f32> {
+        (0..(secs * rate as f64) as usize)
+            .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 / rate as f64).sin() as f32)
+            .collect()
+    }
+
+    /// Largest difference from `expected`, ignoring the filter's edges.
+    fn error(actual: &[f32], expected: &[f32]) -> f32 {
+        let edge = actual.len() / 10;
+        actual[edge..actual.len() - edge]
+            .iter()
+            .zip(&expected[edge..])
+            .map(|(a, e)| (a - e).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn keeps_tones_below_the_new_nyquist_limit() {
+        for (from, to) in [
+            (44_100, 16_000),
+            (48_000, 16_000),
+            (8_000, 16_000),
+            (44_100, 16_001),
+        ] {
+            let resampled = resample(&tone(440.0, from, 0.5), from, to);
+            let expected = tone(440.0, to, 0.5);
+            assert!(resampled.len().abs_diff(expected.len()) <= 1);
+            assert!(error(&resampled, &expected) < 0.01, "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn filters_out_tones_above_the_new_nyquist_limit() {
+        let resampled = resample(&tone(12_000.0, 44_100, 0.5), 44_100, 16_000);
+        assert!(error(&resampled, &vec![0.0; resampled.len()]) < 0.01);
+    }
+
+    #[test]
+    fn downmixes_frames() {
+        assert_eq!(downmix_to_mono(&[1.0, 0.0, 0.5, 0.5], 2), [0.5, 0.5]);
+        assert_eq!(downmix_to_mono(&[1.0, 0.0], 1), [1.0, 0.0]);
+    }
+}
@@ -1,24 +1,109 @@
+use crate::audio::UploadCodec;
+use crate::postprocess::{default_steps, PostProcessStep, RewritePreset};
+use crate::transcription::{
+    BackendKind, HallucinationAction, ProviderConfig, TranscriptionMode, DEFAULT_MODEL,
+    GROQ_API_ENDPOINT, WHISPER_SAMPLE_RATE,
+};
 use anyhow::{Context, Result};
 use keyring::Entry;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_REWRITE_MODEL: &str = "llama-3.3-70b-versatile";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Config {
+    /// Base URL of the OpenAI-compatible API, e.g. `https://api.groq.com/openai/v1`
     pub api_endpoint: String,
+    /// Identifier of the microphone to record from; `None` uses the system default
+    pub input_device: Option<String>,
     pub sample_rate: u32,
     pub channels: u16,
     pub max_file_size_bytes: usize,
+    /// Format of the uploaded audio; compressed formats save bandwidth
+    pub upload_codec: UploadCodec,
     pub show_remove_api_key_button: bool,
+    /// How many times a failed API call is retried before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further attempt
+    pub retry_base_delay_ms: u64,
+    /// Upper bound for the backoff delay (a server's Retry-After is honored up to a minute)
+    pub retry_max_delay_ms: u64,
+    /// HTTP(S) proxy for all provider calls, e.g. `http://proxy.example.com:3128`
+    pub proxy_url: Option<String>,
+    pub connect_timeout_secs: u64,
+    /// Limit for a whole request, including the upload and the provider's processing
+    pub request_timeout_secs: u64,
+    /// PEM files with additional trusted root certificates, e.g. a corporate CA
+    pub extra_root_certificates: Vec<PathBuf>,
+    pub backend: BackendKind,
+    /// Speech model requested from OpenAI-compatible providers
+    pub model: String,
+    /// Providers tried in order when the primary one fails with a retryable error
+    pub fallback_providers: Vec<ProviderConfig>,
+    /// Audio minutes per calendar month after which each upload shows a warning
+    pub soft_quota_minutes: Option<f64>,
+    /// Audio minutes per calendar month after which uploads are refused
+    pub hard_quota_minutes: Option<f64>,
+    /// ISO-639-1 code of the spoken language, or "auto" to let the model detect it
+    pub language: String,
+    pub mode: TranscriptionMode,
+    /// Transcribe utterances in the background while still recording
+    pub live_transcription: bool,
+    /// Pause after which a live utterance is considered finished
+    pub live_silence_ms: u64,
+    /// Segments with a lower average token log probability are highlighted
+    pub low_confidence_logprob: f64,
+    /// Segments more likely than this to contain no speech are highlighted
+    pub low_confidence_no_speech_prob: f64,
+    /// Whether made-up segments (text over silence, loops) are dropped or flagged
+    pub hallucination_action: HallucinationAction,
+    /// Phrases the model invents on silence, in addition to the built-in ones
+    pub hallucination_phrases: Vec<String>,
+    /// Domain terms (product names, people, acronyms) the model should spell correctly
+    pub glossary: Vec<String>,
+    /// Free-text prompt describing the style or context of the recordings
+    pub prompt: String,
+    /// Path to a whisper.cpp GGML model, used by the local backend
+    pub local_model_path: Option<PathBuf>,
+    /// Steps applied in order to every transcript before it is shown
+    pub post_processing: Vec<PostProcessStep>,
+    /// Chat model on the same API that rewrites transcripts
+    pub rewrite_model: String,
+    /// Preset last chosen for rewriting
+    pub rewrite_preset: RewritePreset,
+    // The API key lives in the keyring and is never written to the config file
+    #[serde(skip)]
     pub api_key: Option<String>,
+    /// Why the config file could not be read; while set, `save` leaves the file alone
+    #[serde(skip)]
+    pub load_error: Option<String>,
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
         debug!("Entering Config::load()");
         info!("Loading configuration");
-        let mut config = Self::default();
-        debug!("Default configuration created");
+        let mut config = match Self::load_from_file() {
+            Ok(Some(config)) => {
+                info!("Configuration loaded from {:?}", Self::config_path());
+                config
+            }
+            Ok(None) => {
+                debug!("No configuration file found, using defaults");
+                Self::default()
+            }
+            Err(e) => {
+                error!("Failed to read configuration file, using defaults: {:#}", e);
+                Self {
+                    load_error: Some(format!("{:#}", e)),
+                    ..Self::default()
+                }
+            }
+        };
 
         // Load API key from keyring
         debug!("Attempting to load API key from keyring");
@@ -28,7 +113,7 @@ impl Config {
                 debug!("Validating loaded API key");
 
                 // Validate the loaded API key
-                if Self::is_valid_api_key(&api_key) {
+                if config.is_valid_api_key(&api_key) {
                     info!("Loaded API key is valid");
                     config.api_key = Some(api_key);
                     debug!("API key successfully set in the configuration");
@@ -49,6 +134,45 @@ impl Config {
         debug!("Exiting Config::load()");
         Ok(config)
     }
+
+    pub fn config_path() -> PathBuf {
+        glib::user_config_dir()
+            .join("voice_transcriber")
+            .join("config.json")
+    }
+
+    fn load_from_file() -> Result<Option<Self>> {
+        let path = Self::config_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {:?}", path))?;
+        let config = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {:?}", path))?;
+        Ok(Some(config))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::config_path();
+        // Saving the defaults would throw away the user's settings
+        if self.load_error.is_some() {
+            anyhow::bail!(
+                "{:?} could not be read and is left alone until it is fixed",
+                path
+            );
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create config directory {:?}", dir))?;
+        }
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write config file {:?}", path))?;
+        info!("Configuration saved to {:?}", path);
+        Ok(())
+    }
+
     pub fn get_api_key() -> Result<Option<String>> {
         let entry = Entry::new("com.example.VoiceTranscriber", "api_key")?;
         match entry.get_password() {
@@ -99,15 +223,44 @@ impl Config {
         }
     }
 
-    pub fn max_recording_duration(&self) -> f64 {
-        self.max_file_size_bytes as f64 / (self.sample_rate as f64 * self.channels as f64 * 4.0)
+    /// Length in seconds of the chunks longer recordings are split into: the most
+    /// audio that fits in a single upload as 16-bit WAV. Compressed codecs always
+    /// fit, since their output is smaller than WAV.
+    pub fn max_chunk_secs(&self) -> f64 {
+        // Leave room for the WAV header and the multipart envelope
+        let usable_bytes = self.max_file_size_bytes as f64 * 0.95;
+        // Recordings are downmixed to mono and resampled before upload
+        usable_bytes / (WHISPER_SAMPLE_RATE as f64 * 2.0)
+    }
+
+    /// Base URL without a trailing slash. Older configs stored the full
+    /// transcriptions URL here, so that suffix is stripped as well.
+    pub fn api_base_url(&self) -> String {
+        self.api_endpoint
+            .trim_end_matches('/')
+            .trim_end_matches("/audio/transcriptions")
+            .to_string()
+    }
+
+    /// The provider configured by `backend`, `api_endpoint` and `model`.
+    pub fn primary_provider(&self) -> ProviderConfig {
+        ProviderConfig {
+            name: None,
+            backend: self.backend,
+            api_endpoint: self.api_base_url(),
+            model: self.model.clone(),
+            api_key_env: None,
+        }
     }
 
-    fn is_valid_api_key(api_key: &str) -> bool {
-        // For now, we'll just check if it starts with "gsk_" and is at least 20 characters long
-        // correct API key check can be made by calling the API with request for available models.
-        //  It must return a 200 OK response with some JSON data if key is valid and accepted.
-        let is_valid = api_key.starts_with("gsk_") && api_key.len() >= 20;
+    pub fn is_valid_api_key(&self, api_key: &str) -> bool {
+        // Groq keys start with "gsk_" and are at least 20 characters long. Other
+        // OpenAI-compatible servers use arbitrary tokens, so only empty ones are rejected.
+        // The real check is the /models request made when the key is saved.
+        let is_valid = match self.backend {
+            BackendKind::Groq => api_key.starts_with("gsk_") && api_key.len() >= 20,
+            _ => !api_key.trim().is_empty(),
+        };
         if is_valid {
             info!("API key validation successful");
         } else {
@@ -120,12 +273,41 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
-            api_endpoint: "https://api.groq.com/openai/v1/audio/transcriptions".to_string(),
+            api_endpoint: GROQ_API_ENDPOINT.to_string(),
+            input_device: None,
             sample_rate: 44100,
             channels: 1,
             max_file_size_bytes: 25 * 1024 * 1024, // 25 MB
+            upload_codec: UploadCodec::default(),
             show_remove_api_key_button: false,
+            max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_ms: 30_000,
+            proxy_url: None,
+            connect_timeout_secs: 10,
+            request_timeout_secs: 300,
+            extra_root_certificates: Vec::new(),
+            backend: BackendKind::default(),
+            model: DEFAULT_MODEL.to_string(),
+            fallback_providers: Vec::new(),
+            soft_quota_minutes: None,
+            hard_quota_minutes: None,
+            language: "en".to_string(),
+            mode: TranscriptionMode::default(),
+            live_transcription: false,
+            live_silence_ms: 700,
+            low_confidence_logprob: -1.0,
+            low_confidence_no_speech_prob: 0.6,
+            hallucination_action: HallucinationAction::default(),
+            hallucination_phrases: Vec::new(),
+            glossary: Vec::new(),
+            prompt: String::new(),
+            local_model_path: None,
+            post_processing: default_steps(),
+            rewrite_model: DEFAULT_REWRITE_MODEL.to_string(),
+            rewrite_preset: RewritePreset::default(),
             api_key: None,
+            load_error: None,
         }
     }
 }
@@ -1,17 +1,97 @@
-use crate::audio::{play_audio, record_audio};
+use crate::audio::{list_input_devices, play_audio, record_audio, InputDevice, InputLevel};
 use crate::config::Config;
+use crate::postprocess::RewritePreset;
 use crate::state::{AppStateEnum, StateManager};
+use crate::transcription::{
+    RecoveryAction, TranscribeError, TranscriptionMode, TranscriptionStatus, AUTO_LANGUAGE,
+};
+use crate::usage::{UsageStats, UsageTotals};
+use gstreamer as gst;
 use gtk::prelude::*;
+use log::{error, warn};
+use relm4::gtk::glib;
 use relm4::prelude::*;
 use relm4::{gtk, ComponentParts, ComponentSender, RelmApp, RelmWidgetExt};
+use std::ops::Range;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
 mod audio;
 mod config;
+mod postprocess;
 mod state;
+mod transcription;
+mod usage;
+
+// Offered in the language picker; a configured code outside this list is appended
+const LANGUAGES: &[&str] = &[
+    AUTO_LANGUAGE,
+    "en",
+    "de",
+    "es",
+    "fr",
+    "it",
+    "pt",
+    "nl",
+    "pl",
+    "uk",
+    "ru",
+    "tr",
+    "ja",
+    "zh",
+];
 
 struct AppModel {
     state_manager: Arc<StateManager>,
+    app_state: AppStateEnum,
+    api_key_entry: gtk::PasswordEntry,
+    model_list: gtk::StringList,
+    model_dropdown: gtk::DropDown,
+    model_selected_handler: glib::SignalHandlerId,
+    input_devices: Vec<InputDevice>,
+    input_level: InputLevel,
+    /// Level readings left until the clipping indicator goes out
+    clip_hold: u32,
+    text_view: gtk::TextView,
+    text_buffer: gtk::TextBuffer,
+    /// Character offsets of the highlighted low-confidence passages
+    low_confidence_spans: Vec<(i32, i32)>,
+    review_index: Option<usize>,
+    rewrite_buffer: gtk::TextBuffer,
+    rewrite_preset: RewritePreset,
+    rewriting: bool,
+    rewritten: bool,
+    detected_language: Option<String>,
+    transcript_provider: Option<String>,
+    status_text: String,
+    usage_text: String,
+    error: Option<TranscribeError>,
+    /// What failed with `error`, repeated by the Retry button
+    failed_operation: Option<Operation>,
+}
+
+/// A user action that can fail with a `TranscribeError` and be tried again.
+#[derive(Debug, Clone)]
+enum Operation {
+    Transcribe,
+    SaveApiKey(String),
+    Rewrite,
+    Record,
+    Play,
+    RefreshModels,
+}
+
+impl Operation {
+    fn message(self) -> AppMsg {
+        match self {
+            Operation::Transcribe => AppMsg::Transcribe,
+            Operation::SaveApiKey(api_key) => AppMsg::SetApiKey(api_key),
+            Operation::Rewrite => AppMsg::Rewrite,
+            Operation::Record => AppMsg::Record,
+            Operation::Play => AppMsg::Play,
+            Operation::RefreshModels => AppMsg::RefreshModels,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -19,10 +99,30 @@ enum AppMsg {
     Record,
     Play,
     Transcribe,
+    CancelTranscription,
     Reset,
     UpdateState(AppStateEnum),
     SetApiKey(String),
     RemoveApiKey,
+    RefreshModels,
+    ModelsLoaded(Vec<String>),
+    SelectModel(String),
+    SelectLanguage(String),
+    /// Index into the device picker, where 0 is the system default
+    SelectInputDevice(u32),
+    SetMode(TranscriptionMode),
+    SetLiveTranscription(bool),
+    PartialTranscript(String),
+    LevelChanged(InputLevel),
+    PreviousLowConfidence,
+    NextLowConfidence,
+    SelectRewritePreset(RewritePreset),
+    Rewrite,
+    Rewritten(String),
+    StatusChanged(TranscriptionStatus),
+    RefreshUsage,
+    ShowError(Operation, TranscribeError),
+    Recover,
 }
 
 #[relm4::component]
@@ -42,8 +142,8 @@ impl SimpleComponent for AppModel {
                 set_spacing: 6,
                 set_margin_all: 12,
 
-                #[name = "api_key_entry"]
-                gtk::PasswordEntry {
+                #[local_ref]
+                api_key_entry -> gtk::PasswordEntry {
                     set_placeholder_text: Some("Enter Groq API Key"),
                     set_show_peek_icon: true,
                 },
@@ -65,6 +165,68 @@ impl SimpleComponent for AppModel {
                     },
                 },
 
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+
+                    #[local_ref]
+                    model_dropdown -> gtk::DropDown {
+                        set_hexpand: true,
+                    },
+
+                    gtk::DropDown::from_strings(&languages) {
+                        set_tooltip_text: Some("Spoken Language"),
+                        set_selected: selected_language,
+                        connect_selected_notify[sender] => move |dropdown| {
+                            if let Some(item) = dropdown
+                                .selected_item()
+                                .and_downcast::<gtk::StringObject>()
+                            {
+                                sender.input(AppMsg::SelectLanguage(item.string().to_string()));
+                            }
+                        },
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "view-refresh-symbolic",
+                        set_tooltip_text: Some("Refresh Models"),
+                        connect_clicked[sender] => move |_| {
+                            sender.input(AppMsg::RefreshModels);
+                        },
+                    },
+                },
+
+                gtk::DropDown::from_strings(&input_device_names) {
+                    set_tooltip_text: Some("Input Device"),
+                    set_selected: selected_input_device,
+                    connect_selected_notify[sender] => move |dropdown| {
+                        sender.input(AppMsg::SelectInputDevice(dropdown.selected()));
+                    },
+                },
+
+                #[name = "translate_check"]
+                gtk::CheckButton {
+                    set_label: Some("Translate to English"),
+                    set_active: translate,
+                    connect_toggled[sender] => move |check| {
+                        sender.input(AppMsg::SetMode(if check.is_active() {
+                            TranscriptionMode::Translate
+                        } else {
+                            TranscriptionMode::Transcribe
+                        }));
+                    },
+                },
+
+                #[name = "live_check"]
+                gtk::CheckButton {
+                    set_label: Some("Live transcription"),
+                    set_tooltip_text: Some("Transcribe while recording, pausing between sentences"),
+                    set_active: live_transcription,
+                    connect_toggled[sender] => move |check| {
+                        sender.input(AppMsg::SetLiveTranscription(check.is_active()));
+                    },
+                },
+
                 #[name = "record_button"]
                 gtk::Button {
                     set_label: "Start Recording",
@@ -73,14 +235,65 @@ impl SimpleComponent for AppModel {
                     },
                 },
 
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+                    #[watch]
+                    set_visible: model.app_state == AppStateEnum::Recording,
+
+                    #[name = "level_bar"]
+                    gtk::LevelBar {
+                        set_hexpand: true,
+                        set_valign: gtk::Align::Center,
+                        set_min_value: 0.0,
+                        set_max_value: 1.0,
+                        set_tooltip_text: Some("Input Level"),
+                        #[watch]
+                        set_value: meter_value(model.input_level.rms),
+                    },
+
+                    #[name = "peak_label"]
+                    gtk::Label {
+                        add_css_class: "monospace",
+                        set_tooltip_text: Some("Peak Level"),
+                        #[watch]
+                        set_label: &format!(
+                            "{:>4.0} dB",
+                            InputLevel::to_dbfs(model.input_level.peak, METER_MIN_DB)
+                        ),
+                    },
+
+                    #[name = "clip_label"]
+                    gtk::Label {
+                        set_markup: "<span color=\"red\" weight=\"bold\">CLIP</span>",
+                        set_tooltip_text: Some("The input is too loud; lower the microphone gain"),
+                        #[watch]
+                        set_visible: model.clip_hold > 0,
+                    },
+                },
+
                 #[name = "transcribe_button"]
                 gtk::Button {
                     set_label: "Transcribe",
+                    #[watch]
+                    set_sensitive: model.app_state != AppStateEnum::Transcribing,
                     connect_clicked[sender] => move |_| {
                         sender.input(AppMsg::Transcribe);
                     },
                 },
 
+                #[name = "cancel_button"]
+                gtk::Button {
+                    set_label: "Cancel Transcription",
+                    #[watch]
+                    set_visible: model.app_state == AppStateEnum::Transcribing
+                        || (model.app_state == AppStateEnum::Recording
+                            && model.state_manager.has_transcription_job()),
+                    connect_clicked[sender] => move |_| {
+                        sender.input(AppMsg::CancelTranscription);
+                    },
+                },
+
                 #[name = "play_button"]
                 gtk::Button {
                     set_label: "Play Recording",
@@ -97,16 +310,171 @@ impl SimpleComponent for AppModel {
                     },
                 },
 
-                #[name = "text_view"]
-                gtk::TextView {
-                    set_editable: false,
-                    set_wrap_mode: gtk::WrapMode::Word,
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+                    set_homogeneous: true,
+
+                    #[local_ref]
+                    text_view -> gtk::TextView {
+                        set_editable: false,
+                        set_wrap_mode: gtk::WrapMode::Word,
+                        set_buffer: Some(&model.text_buffer),
+                    },
+
+                    #[name = "rewrite_view"]
+                    gtk::TextView {
+                        set_editable: false,
+                        set_wrap_mode: gtk::WrapMode::Word,
+                        set_buffer: Some(&model.rewrite_buffer),
+                        #[watch]
+                        set_visible: model.rewritten,
+                    },
                 },
 
-                #[name = "error_label"]
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+                    #[watch]
+                    set_visible: !model.low_confidence_spans.is_empty(),
+
+                    #[name = "review_label"]
+                    gtk::Label {
+                        set_hexpand: true,
+                        set_xalign: 0.0,
+                        #[watch]
+                        set_label: &model.review_text(),
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "go-up-symbolic",
+                        set_tooltip_text: Some("Previous Low-Confidence Passage"),
+                        connect_clicked[sender] => move |_| {
+                            sender.input(AppMsg::PreviousLowConfidence);
+                        },
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "go-down-symbolic",
+                        set_tooltip_text: Some("Next Low-Confidence Passage"),
+                        connect_clicked[sender] => move |_| {
+                            sender.input(AppMsg::NextLowConfidence);
+                        },
+                    },
+                },
+
+                #[name = "language_label"]
+                gtk::Label {
+                    set_halign: gtk::Align::End,
+                    add_css_class: "dim-label",
+                    #[watch]
+                    set_visible: model.detected_language.is_some(),
+                    #[watch]
+                    set_label: &format!(
+                        "Language: {}",
+                        model.detected_language.as_deref().unwrap_or_default()
+                    ),
+                },
+
+                #[name = "provider_label"]
+                gtk::Label {
+                    set_halign: gtk::Align::End,
+                    add_css_class: "dim-label",
+                    #[watch]
+                    set_visible: model.transcript_provider.is_some(),
+                    #[watch]
+                    set_label: &format!(
+                        "Transcribed by {}",
+                        model.transcript_provider.as_deref().unwrap_or_default()
+                    ),
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+
+                    gtk::DropDown::from_strings(&rewrite_presets) {
+                        set_hexpand: true,
+                        set_tooltip_text: Some("Rewrite Style"),
+                        set_selected: selected_rewrite_preset,
+                        connect_selected_notify[sender] => move |dropdown| {
+                            if let Some(preset) = RewritePreset::ALL.get(dropdown.selected() as usize) {
+                                sender.input(AppMsg::SelectRewritePreset(*preset));
+                            }
+                        },
+                    },
+
+                    #[name = "rewrite_button"]
+                    gtk::Button {
+                        #[watch]
+                        set_label: if model.rewriting { "Rewriting…" } else { "Rewrite" },
+                        #[watch]
+                        set_sensitive: model.app_state == AppStateEnum::Transcribed && !model.rewriting,
+                        connect_clicked[sender] => move |_| {
+                            sender.input(AppMsg::Rewrite);
+                        },
+                    },
+                },
+
+                #[name = "usage_expander"]
+                gtk::Expander {
+                    set_label: Some("Usage"),
+                    connect_expanded_notify[sender] => move |expander| {
+                        if expander.is_expanded() {
+                            sender.input(AppMsg::RefreshUsage);
+                        }
+                    },
+
+                    #[name = "usage_label"]
+                    gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_selectable: true,
+                        add_css_class: "monospace",
+                        #[watch]
+                        set_label: &model.usage_text,
+                    },
+                },
+
+                #[name = "status_label"]
                 gtk::Label {
-                    set_markup: "<span color=\"red\"></span>",
-                    set_visible: false,
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: !model.status_text.is_empty(),
+                    #[watch]
+                    set_label: &model.status_text,
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+                    #[watch]
+                    set_visible: model.error.is_some(),
+
+                    #[name = "error_label"]
+                    gtk::Label {
+                        set_hexpand: true,
+                        set_wrap: true,
+                        set_xalign: 0.0,
+                        #[watch]
+                        set_markup: &format!(
+                            "<span color=\"red\">{}</span>",
+                            glib::markup_escape_text(
+                                &model.error.as_ref().map(TranscribeError::user_message).unwrap_or_default()
+                            )
+                        ),
+                    },
+
+                    #[name = "recovery_button"]
+                    gtk::Button {
+                        set_valign: gtk::Align::Center,
+                        #[watch]
+                        set_visible: model.recovery_action().is_some(),
+                        #[watch]
+                        set_label: model.recovery_action().map(|action| action.label()).unwrap_or_default(),
+                        connect_clicked[sender] => move |_| {
+                            sender.input(AppMsg::Recover);
+                        },
+                    },
                 }
             }
         }
@@ -117,9 +485,126 @@ impl SimpleComponent for AppModel {
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = AppModel { state_manager };
+        let api_key_entry = gtk::PasswordEntry::new();
+        let text_view = gtk::TextView::new();
+        let text_buffer = gtk::TextBuffer::new(None);
+        text_buffer.tag_table().add(
+            &gtk::TextTag::builder()
+                .name(LOW_CONFIDENCE_TAG)
+                .background("rgba(245, 194, 17, 0.4)")
+                .build(),
+        );
+
+        // Until the provider's list arrives, offer only the configured model
+        let model_list = gtk::StringList::new(&[state_manager.get_config().model.as_str()]);
+        let model_dropdown = gtk::DropDown::builder().model(&model_list).build();
+        let model_selected_handler = model_dropdown.connect_selected_notify({
+            let sender = sender.clone();
+            move |dropdown| {
+                if let Some(item) = dropdown.selected_item().and_downcast::<gtk::StringObject>() {
+                    sender.input(AppMsg::SelectModel(item.string().to_string()));
+                }
+            }
+        });
+
+        // Shown right away, as nothing can reach a provider until it is fixed
+        let mut startup_error = state_manager
+            .get_config()
+            .load_error
+            .map(TranscribeError::InvalidConfig)
+            .or_else(|| state_manager.http_client().err());
+        let input_devices = list_input_devices().unwrap_or_else(|e| {
+            error!("Error listing input devices: {:#}", e);
+            startup_error.get_or_insert(TranscribeError::AudioDevice(format!("{:#}", e)));
+            Vec::new()
+        });
+        let input_device = state_manager.get_config().input_device;
+        let input_device_names: Vec<&str> = std::iter::once("Default Input")
+            .chain(input_devices.iter().map(|device| device.name.as_str()))
+            .collect();
+        let selected_input_device = input_devices
+            .iter()
+            .position(|device| Some(&device.id) == input_device.as_ref())
+            .map_or(0, |index| index as u32 + 1);
+
+        let translate = state_manager.get_config().mode == TranscriptionMode::Translate;
+        let live_transcription = state_manager.get_config().live_transcription;
+        let language = state_manager.get_config().language;
+        let mut languages = LANGUAGES.to_vec();
+        if !languages.contains(&language.as_str()) {
+            languages.push(&language);
+        }
+        let selected_language = languages
+            .iter()
+            .position(|code| *code == language)
+            .unwrap_or(0) as u32;
+        let rewrite_preset = state_manager.get_config().rewrite_preset;
+        let rewrite_presets: Vec<&str> = RewritePreset::ALL
+            .iter()
+            .map(RewritePreset::label)
+            .collect();
+        let selected_rewrite_preset = RewritePreset::ALL
+            .iter()
+            .position(|preset| *preset == rewrite_preset)
+            .unwrap_or(0) as u32;
+
+        let model = AppModel {
+            app_state: state_manager.get_app_state(),
+            state_manager,
+            api_key_entry: api_key_entry.clone(),
+            model_list,
+            model_dropdown: model_dropdown.clone(),
+            model_selected_handler,
+            input_devices: input_devices.clone(),
+            input_level: InputLevel::default(),
+            clip_hold: 0,
+            text_view: text_view.clone(),
+            text_buffer,
+            low_confidence_spans: Vec::new(),
+            review_index: None,
+            rewrite_buffer: gtk::TextBuffer::new(None),
+            rewrite_preset,
+            rewriting: false,
+            rewritten: false,
+            detected_language: None,
+            transcript_provider: None,
+            status_text: String::new(),
+            usage_text: String::new(),
+            error: startup_error,
+            failed_operation: None,
+        };
         let widgets = view_output!();
 
+        let mut status_receiver = model.state_manager.subscribe_status();
+        let status_sender = sender.clone();
+        tokio::spawn(async move {
+            loop {
+                match status_receiver.recv().await {
+                    Ok(status) => status_sender.input(AppMsg::StatusChanged(status)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let mut level_receiver = model.state_manager.subscribe_level();
+        let level_sender = sender.clone();
+        tokio::spawn(async move {
+            while level_receiver.changed().await.is_ok() {
+                let level = *level_receiver.borrow_and_update();
+                level_sender.input(AppMsg::LevelChanged(level));
+            }
+        });
+
+        let cached_models = model.state_manager.get_available_models();
+        if !cached_models.is_empty() {
+            sender.input(AppMsg::ModelsLoaded(cached_models));
+        }
+        // Without a key the provider would only answer 401 on first launch
+        if !model.state_manager.is_api_key_missing() {
+            sender.input(AppMsg::RefreshModels);
+        }
+
         ComponentParts { model, widgets }
     }
 
@@ -127,16 +612,64 @@ impl SimpleComponent for AppModel {
         match msg {
             AppMsg::Record => {
                 if !self.state_manager.is_recording() {
-                    self.state_manager.set_app_state(AppStateEnum::Recording);
+                    // Before anything reads the recording, so live transcription starts on the new one
+                    let stop_receiver = self.state_manager.start_recording();
                     sender.input(AppMsg::UpdateState(AppStateEnum::Recording));
                     let state_manager = Arc::clone(&self.state_manager);
                     let sender_clone = sender.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = record_audio(state_manager).await {
-                            eprintln!("Error during recording: {}", e);
+                        let result = record_audio(Arc::clone(&state_manager), stop_receiver).await;
+                        if result.is_err() {
+                            // Leaves the recording state, which live transcription waits on
+                            state_manager.stop_recording();
                         }
                         sender_clone.input(AppMsg::UpdateState(AppStateEnum::Recorded));
+                        if let Err(e) = result {
+                            error!("Error during recording: {:#}", e);
+                            sender_clone.input(AppMsg::ShowError(
+                                Operation::Record,
+                                TranscribeError::AudioDevice(format!("{:#}", e)),
+                            ));
+                        }
                     });
+
+                    if self.state_manager.get_config().live_transcription {
+                        self.error = None;
+                        let state_manager = Arc::clone(&self.state_manager);
+                        let sender_clone = sender.clone();
+                        tokio::spawn(async move {
+                            let partial_sender = sender_clone.clone();
+                            let result = state_manager
+                                .transcribe_live(move |transcript| {
+                                    partial_sender
+                                        .input(AppMsg::PartialTranscript(transcript.text.clone()));
+                                })
+                                .await;
+                            match result {
+                                // Nothing was said, or the recorder failed and shows its own error
+                                Ok((transcript, None)) if transcript.text.is_empty() => {}
+                                Ok((transcript, stopped_by)) => {
+                                    state_manager.set_transcript(transcript);
+                                    sender_clone
+                                        .input(AppMsg::UpdateState(AppStateEnum::Transcribed));
+                                    if let Some(e) = stopped_by {
+                                        sender_clone
+                                            .input(AppMsg::ShowError(Operation::Transcribe, e));
+                                    }
+                                }
+                                // The recording goes on and can be transcribed in one go later
+                                Err(TranscribeError::Cancelled) => {
+                                    sender_clone
+                                        .input(AppMsg::UpdateState(state_manager.get_app_state()));
+                                }
+                                Err(e) => {
+                                    error!("Live transcription error: {}", e);
+                                    // The recording is kept, so a retry transcribes it in one go
+                                    sender_clone.input(AppMsg::ShowError(Operation::Transcribe, e));
+                                }
+                            }
+                        });
+                    }
                 } else {
                     self.state_manager.stop_recording();
                     sender.input(AppMsg::UpdateState(AppStateEnum::Recorded));
@@ -149,10 +682,15 @@ impl SimpleComponent for AppModel {
                     let state_manager = Arc::clone(&self.state_manager);
                     let sender_clone = sender.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = play_audio(state_manager).await {
-                            eprintln!("Error playing audio: {}", e);
-                        }
+                        let result = play_audio(state_manager).await;
                         sender_clone.input(AppMsg::UpdateState(AppStateEnum::Recorded));
+                        if let Err(e) = result {
+                            error!("Error playing audio: {:#}", e);
+                            sender_clone.input(AppMsg::ShowError(
+                                Operation::Play,
+                                TranscribeError::AudioDevice(format!("{:#}", e)),
+                            ));
+                        }
                     });
                 } else {
                     self.state_manager.stop_playing();
@@ -160,59 +698,388 @@ impl SimpleComponent for AppModel {
                 }
             }
             AppMsg::Transcribe => {
+                self.error = None;
+                self.app_state = AppStateEnum::Transcribing;
                 let state_manager = Arc::clone(&self.state_manager);
                 let sender_clone = sender.clone();
                 tokio::spawn(async move {
                     match state_manager.transcribe_audio().await {
-                        Ok(transcription) => {
-                            state_manager.set_transcribed_text(transcription);
+                        Ok(transcript) => {
+                            state_manager.set_transcript(transcript);
                             sender_clone.input(AppMsg::UpdateState(AppStateEnum::Transcribed));
                         }
+                        Err(TranscribeError::Cancelled) => {
+                            sender_clone.input(AppMsg::UpdateState(state_manager.get_app_state()));
+                        }
                         Err(e) => {
-                            eprintln!("Transcription error: {}", e);
-                            // TODO: Update error label
+                            error!("Transcription error: {}", e);
+                            sender_clone.input(AppMsg::ShowError(Operation::Transcribe, e));
                         }
                     }
                 });
             }
+            AppMsg::CancelTranscription => {
+                self.state_manager.cancel_transcription();
+            }
             AppMsg::Reset => {
+                self.state_manager.cancel_transcription();
                 self.state_manager.clear_audio_data();
-                self.state_manager.set_transcribed_text(String::new());
+                self.state_manager.clear_transcript();
                 self.state_manager.set_app_state(AppStateEnum::Initial);
                 sender.input(AppMsg::UpdateState(AppStateEnum::Initial));
             }
-            AppMsg::UpdateState(_state) => {
-                // TODO: Implement UI update logic
+            AppMsg::UpdateState(state) => {
+                self.app_state = state;
+                let (text, spans) = self.state_manager.get_text_with_low_confidence_spans();
+                self.text_buffer.set_text(&text);
+                self.highlight_low_confidence(&text, &spans);
+                let rewritten_text = self.state_manager.get_rewritten_text();
+                self.rewritten = rewritten_text.is_some();
+                self.rewrite_buffer
+                    .set_text(rewritten_text.as_deref().unwrap_or_default());
+                self.detected_language = self.state_manager.get_detected_language();
+                self.transcript_provider = self
+                    .state_manager
+                    .get_transcript()
+                    .and_then(|transcript| transcript.provider);
+                self.status_text.clear();
+                self.error = None;
+                if self.app_state == AppStateEnum::Transcribed {
+                    sender.input(AppMsg::RefreshUsage);
+                }
             }
             AppMsg::SetApiKey(api_key) => {
                 let state_manager = Arc::clone(&self.state_manager);
                 let sender_clone = sender.clone();
                 tokio::spawn(async move {
                     match state_manager.validate_and_save_api_key(&api_key).await {
-                        Ok(true) => {
+                        Ok(()) => {
                             sender_clone.input(AppMsg::UpdateState(AppStateEnum::Initial));
-                        }
-                        Ok(false) => {
-                            // TODO: Update error label
+                            sender_clone.input(AppMsg::RefreshModels);
                         }
                         Err(e) => {
-                            eprintln!("Error saving API key: {}", e);
-                            // TODO: Update error label
+                            error!("Error saving API key: {}", e);
+                            sender_clone
+                                .input(AppMsg::ShowError(Operation::SaveApiKey(api_key), e));
                         }
                     }
                 });
             }
             AppMsg::RemoveApiKey => {
                 if let Err(e) = self.state_manager.remove_api_key() {
-                    eprintln!("Error removing API key: {}", e);
-                    // TODO: Update error label
+                    error!("Error removing API key: {}", e);
+                    self.show_error(e);
+                }
+            }
+            AppMsg::RefreshModels => {
+                let state_manager = Arc::clone(&self.state_manager);
+                let sender_clone = sender.clone();
+                tokio::spawn(async move {
+                    match state_manager.refresh_models().await {
+                        Ok(models) => sender_clone.input(AppMsg::ModelsLoaded(models)),
+                        Err(e) => {
+                            warn!("Error fetching models: {}", e);
+                            sender_clone.input(AppMsg::ShowError(Operation::RefreshModels, e));
+                        }
+                    }
+                });
+            }
+            AppMsg::ModelsLoaded(mut models) => {
+                let current = self.state_manager.get_config().model;
+                if !models.contains(&current) {
+                    models.insert(0, current.clone());
+                }
+                let names: Vec<&str> = models.iter().map(String::as_str).collect();
+                let selected = models.iter().position(|m| *m == current).unwrap_or(0);
+
+                // Repopulating resets the selection, which must not overwrite the saved choice
+                self.model_dropdown
+                    .block_signal(&self.model_selected_handler);
+                self.model_list.splice(0, self.model_list.n_items(), &names);
+                self.model_dropdown.set_selected(selected as u32);
+                self.model_dropdown
+                    .unblock_signal(&self.model_selected_handler);
+            }
+            AppMsg::SelectModel(model) => {
+                if let Err(e) = self.state_manager.set_model(&model) {
+                    error!("Error saving model selection: {:#}", e);
+                    self.show_error(TranscribeError::Settings(format!("{:#}", e)));
+                }
+            }
+            AppMsg::SelectLanguage(language) => {
+                if let Err(e) = self.state_manager.set_language(&language) {
+                    error!("Error saving language selection: {:#}", e);
+                    self.show_error(TranscribeError::Settings(format!("{:#}", e)));
+                }
+            }
+            AppMsg::SelectInputDevice(index) => {
+                let device_id = index
+                    .checked_sub(1)
+                    .and_then(|index| self.input_devices.get(index as usize))
+                    .map(|device| device.id.clone());
+                if let Err(e) = self.state_manager.set_input_device(device_id) {
+                    error!("Error saving input device selection: {:#}", e);
+                    self.show_error(TranscribeError::Settings(format!("{:#}", e)));
+                }
+            }
+            AppMsg::SetMode(mode) => {
+                if let Err(e) = self.state_manager.set_mode(mode) {
+                    error!("Error saving transcription mode: {:#}", e);
+                    self.show_error(TranscribeError::Settings(format!("{:#}", e)));
+                }
+            }
+            AppMsg::SetLiveTranscription(enabled) => {
+                if let Err(e) = self.state_manager.set_live_transcription(enabled) {
+                    error!("Error saving live transcription setting: {:#}", e);
+                    self.show_error(TranscribeError::Settings(format!("{:#}", e)));
+                }
+            }
+            AppMsg::PartialTranscript(text) => {
+                self.text_buffer.set_text(&text);
+                self.low_confidence_spans.clear();
+                self.review_index = None;
+            }
+            AppMsg::LevelChanged(level) => {
+                self.clip_hold = if level.clipped {
+                    CLIP_HOLD_READINGS
+                } else {
+                    self.clip_hold.saturating_sub(1)
+                };
+                self.input_level = level;
+            }
+            AppMsg::PreviousLowConfidence => {
+                let count = self.low_confidence_spans.len();
+                if count > 0 {
+                    let index = self
+                        .review_index
+                        .map_or(count - 1, |i| (i + count - 1) % count);
+                    self.review_low_confidence(index);
+                }
+            }
+            AppMsg::NextLowConfidence => {
+                let count = self.low_confidence_spans.len();
+                if count > 0 {
+                    let index = self.review_index.map_or(0, |i| (i + 1) % count);
+                    self.review_low_confidence(index);
+                }
+            }
+            AppMsg::SelectRewritePreset(preset) => {
+                self.rewrite_preset = preset;
+                if let Err(e) = self.state_manager.set_rewrite_preset(preset) {
+                    error!("Error saving rewrite preset: {:#}", e);
+                    self.show_error(TranscribeError::Settings(format!("{:#}", e)));
+                }
+            }
+            AppMsg::Rewrite => {
+                self.error = None;
+                self.rewriting = true;
+                let preset = self.rewrite_preset;
+                let state_manager = Arc::clone(&self.state_manager);
+                let sender_clone = sender.clone();
+                tokio::spawn(async move {
+                    match state_manager.rewrite_transcript(preset).await {
+                        Ok(text) => sender_clone.input(AppMsg::Rewritten(text)),
+                        Err(e) => {
+                            error!("Rewrite error: {}", e);
+                            sender_clone.input(AppMsg::ShowError(Operation::Rewrite, e));
+                        }
+                    }
+                });
+            }
+            AppMsg::Rewritten(text) => {
+                self.rewriting = false;
+                self.rewritten = true;
+                self.rewrite_buffer.set_text(&text);
+                self.status_text.clear();
+            }
+            AppMsg::RefreshUsage => match self.state_manager.usage_stats() {
+                Ok(stats) => {
+                    self.usage_text = format_usage(&stats, &self.state_manager.get_config());
+                }
+                Err(e) => {
+                    warn!("Error reading usage log: {:#}", e);
+                }
+            },
+            AppMsg::ShowError(operation, error) => {
+                self.app_state = self.state_manager.get_app_state();
+                self.rewriting = false;
+                self.status_text.clear();
+                self.error = Some(error);
+                self.failed_operation = Some(operation);
+            }
+            AppMsg::Recover => match self.recovery_action() {
+                Some(RecoveryAction::EnterApiKey) => {
+                    self.error = None;
+                    self.api_key_entry.grab_focus();
                 }
+                Some(RecoveryAction::Retry) => {
+                    if let Some(operation) = self.failed_operation.take() {
+                        sender.input(operation.message());
+                    }
+                }
+                Some(RecoveryAction::RecordAgain) => {
+                    sender.input(AppMsg::Reset);
+                    sender.input(AppMsg::Record);
+                }
+                None => {}
+            },
+            AppMsg::StatusChanged(status) => {
+                self.status_text = match status {
+                    TranscriptionStatus::RateLimited { wait } => format!(
+                        "Waiting for rate limit ({:.0}s)…",
+                        wait.as_secs_f64().ceil()
+                    ),
+                    TranscriptionStatus::Retrying {
+                        attempt,
+                        max_retries,
+                        delay,
+                    } => format!(
+                        "Request failed, retrying in {:.1}s (attempt {} of {})…",
+                        delay.as_secs_f64(),
+                        attempt,
+                        max_retries
+                    ),
+                    TranscriptionStatus::FallingBack { failed, next } => {
+                        format!("{} is unavailable, trying {}…", failed, next)
+                    }
+                    TranscriptionStatus::QuotaWarning {
+                        used_minutes,
+                        quota_minutes,
+                    } => format!(
+                        "Over the monthly soft quota: {:.1} of {:.0} audio minutes used",
+                        used_minutes, quota_minutes
+                    ),
+                    TranscriptionStatus::UtteranceFailed { message } => {
+                        format!("A passage was skipped: {}", message)
+                    }
+                };
             }
         }
     }
 }
 
+impl AppModel {
+    /// Shows an error that has nothing to retry.
+    fn show_error(&mut self, error: TranscribeError) {
+        self.error = Some(error);
+        self.failed_operation = None;
+    }
+
+    fn recovery_action(&self) -> Option<RecoveryAction> {
+        self.error
+            .as_ref()
+            .and_then(TranscribeError::recovery_action)
+            .filter(|action| *action != RecoveryAction::Retry || self.failed_operation.is_some())
+    }
+
+    /// Tags the passages of the shown transcript the provider was unsure about.
+    fn highlight_low_confidence(&mut self, text: &str, spans: &[Range<usize>]) {
+        // The buffer counts characters, the transcript bytes
+        let char_offset = |byte: usize| text[..byte].chars().count() as i32;
+        self.low_confidence_spans = spans
+            .iter()
+            .map(|span| (char_offset(span.start), char_offset(span.end)))
+            .collect();
+        self.review_index = None;
+        for (start, end) in &self.low_confidence_spans {
+            self.text_buffer.apply_tag_by_name(
+                LOW_CONFIDENCE_TAG,
+                &self.text_buffer.iter_at_offset(*start),
+                &self.text_buffer.iter_at_offset(*end),
+            );
+        }
+    }
+
+    /// Selects a low-confidence passage and scrolls it into view.
+    fn review_low_confidence(&mut self, index: usize) {
+        let Some(&(start, end)) = self.low_confidence_spans.get(index) else {
+            return;
+        };
+        self.review_index = Some(index);
+        let mut start = self.text_buffer.iter_at_offset(start);
+        let end = self.text_buffer.iter_at_offset(end);
+        self.text_buffer.select_range(&start, &end);
+        self.text_view
+            .scroll_to_iter(&mut start, 0.1, false, 0.0, 0.0);
+        self.text_view.grab_focus();
+    }
+
+    fn review_text(&self) -> String {
+        let count = self.low_confidence_spans.len();
+        match self.review_index {
+            Some(index) => format!("Low-confidence passage {} of {}", index + 1, count),
+            None => format!("{} low-confidence passage(s) highlighted", count),
+        }
+    }
+}
+
+/// Quietest level the meter shows, in dBFS.
+const METER_MIN_DB: f32 = -60.0;
+/// The clipping indicator stays lit for this many level readings, about a second.
+const CLIP_HOLD_READINGS: u32 = 20;
+
+/// Position on the level bar for `amplitude`, on a decibel scale.
+fn meter_value(amplitude: f32) -> f64 {
+    ((InputLevel::to_dbfs(amplitude, METER_MIN_DB) - METER_MIN_DB) / -METER_MIN_DB) as f64
+}
+
+/// Name of the text tag marking passages the provider was unsure about.
+const LOW_CONFIDENCE_TAG: &str = "low-confidence";
+
+/// How many past days and months the usage view lists.
+const USAGE_HISTORY: usize = 7;
+
+fn format_totals(totals: &UsageTotals) -> String {
+    let mut text = format!(
+        "{:7.1} min  {:4} requests  {:7.1} MB",
+        totals.audio_minutes(),
+        totals.requests,
+        totals.bytes as f64 / (1024.0 * 1024.0)
+    );
+    if totals.failed > 0 {
+        text.push_str(&format!(
+            "  ({} failed, {:.1} min)",
+            totals.failed,
+            totals.failed_audio_secs / 60.0
+        ));
+    }
+    text
+}
+
+fn format_usage(stats: &UsageStats, config: &Config) -> String {
+    let mut lines = vec![
+        format!("Today       {}", format_totals(&stats.today())),
+        format!("This month  {}", format_totals(&stats.this_month())),
+    ];
+    let used_minutes = stats.this_month().audio_minutes();
+    for (label, quota) in [
+        ("Soft quota", config.soft_quota_minutes),
+        ("Hard quota", config.hard_quota_minutes),
+    ] {
+        if let Some(quota) = quota {
+            lines.push(format!(
+                "{}: {:.1} of {:.0} minutes",
+                label, used_minutes, quota
+            ));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push("Daily".to_string());
+    for (day, totals) in stats.daily.iter().rev().take(USAGE_HISTORY) {
+        lines.push(format!("{}  {}", day, format_totals(totals)));
+    }
+    lines.push(String::new());
+    lines.push("Monthly".to_string());
+    for (month, totals) in stats.monthly.iter().rev().take(USAGE_HISTORY) {
+        lines.push(format!("{}     {}", month, format_totals(totals)));
+    }
+    lines.join("\n")
+}
+
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    gst::init().expect("Failed to initialize GStreamer");
     let config = Config::load().expect("Failed to load config");
     let state_manager = Arc::new(StateManager::new(config));
 
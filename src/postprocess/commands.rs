@@ -0,0 +1,309 @@
+use super::{language_code, PostProcessor};
+use anyhow::{Context, Result};
+use log::debug;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What a spoken command does to the text around it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DictationAction {
+    /// Attached to the previous word, e.g. "." or a closing quote
+    Punctuation(String),
+    /// Attached to the next word, e.g. an opening quote or bracket
+    Opening(String),
+    NewLine,
+    NewParagraph,
+    /// Deletes the sentence spoken before the command
+    ScratchThat,
+}
+
+/// Built-in commands for `language`, an ISO-639-1 code or an English language
+/// name as some providers report it.
+pub fn builtin_commands(language: &str) -> BTreeMap<String, DictationAction> {
+    use DictationAction::*;
+    let punctuation = |text: &str| Punctuation(text.to_string());
+    let opening = |text: &str| Opening(text.to_string());
+    let commands = match language_code(language).as_str() {
+        "en" => vec![
+            ("period", punctuation(".")),
+            ("full stop", punctuation(".")),
+            ("comma", punctuation(",")),
+            ("question mark", punctuation("?")),
+            ("exclamation mark", punctuation("!")),
+            ("exclamation point", punctuation("!")),
+            ("colon", punctuation(":")),
+            ("semicolon", punctuation(";")),
+            ("open quote", opening("\"")),
+            ("close quote", punctuation("\"")),
+            ("open bracket", opening("(")),
+            ("close bracket", punctuation(")")),
+            ("new line", NewLine),
+            ("new paragraph", NewParagraph),
+            ("scratch that", ScratchThat),
+        ],
+        "de" => vec![
+            ("punkt", punctuation(".")),
+            ("komma", punctuation(",")),
+            ("fragezeichen", punctuation("?")),
+            ("ausrufezeichen", punctuation("!")),
+            ("doppelpunkt", punctuation(":")),
+            ("semikolon", punctuation(";")),
+            ("anführungszeichen auf", opening("„")),
+            ("anführungszeichen zu", punctuation("“")),
+            ("klammer auf", opening("(")),
+            ("klammer zu", punctuation(")")),
+            ("neue zeile", NewLine),
+            ("neuer absatz", NewParagraph),
+            ("streich das", ScratchThat),
+        ],
+        "fr" => vec![
+            ("point", punctuation(".")),
+            ("virgule", punctuation(",")),
+            ("point d'interrogation", punctuation(" ?")),
+            ("point d'exclamation", punctuation(" !")),
+            ("deux points", punctuation(" :")),
+            ("point virgule", punctuation(" ;")),
+            ("ouvrez les guillemets", opening("« ")),
+            ("fermez les guillemets", punctuation(" »")),
+            ("ouvrez la parenthèse", opening("(")),
+            ("fermez la parenthèse", punctuation(")")),
+            ("à la ligne", NewLine),
+            ("nouveau paragraphe", NewParagraph),
+            ("efface ça", ScratchThat),
+        ],
+        "es" => vec![
+            ("punto", punctuation(".")),
+            ("coma", punctuation(",")),
+            ("signo de interrogación", punctuation("?")),
+            ("signo de exclamación", punctuation("!")),
+            ("dos puntos", punctuation(":")),
+            ("punto y coma", punctuation(";")),
+            ("abrir comillas", opening("«")),
+            ("cerrar comillas", punctuation("»")),
+            ("abrir paréntesis", opening("(")),
+            ("cerrar paréntesis", punctuation(")")),
+            ("nueva línea", NewLine),
+            ("nuevo párrafo", NewParagraph),
+            ("borra eso", ScratchThat),
+        ],
+        _ => Vec::new(),
+    };
+    commands
+        .into_iter()
+        .map(|(phrase, action)| (phrase.to_string(), action))
+        .collect()
+}
+
+/// Interprets spoken punctuation and formatting commands in the transcript.
+pub struct DictationCommands {
+    regex: Option<Regex>,
+    actions: BTreeMap<String, DictationAction>,
+}
+
+impl DictationCommands {
+    /// Commands are matched case-insensitively; `overrides` extend or replace
+    /// the built-in ones of the same phrase.
+    pub fn new(language: &str, overrides: &BTreeMap<String, DictationAction>) -> Result<Self> {
+        let mut actions = builtin_commands(language);
+        actions.extend(
+            overrides
+                .iter()
+                .map(|(phrase, action)| (normalize(phrase), action.clone())),
+        );
+        actions.retain(|phrase, _| !phrase.is_empty());
+        if actions.is_empty() {
+            return Ok(Self {
+                regex: None,
+                actions,
+            });
+        }
+
+        // Longer phrases first, so "point virgule" wins over "point"
+        let mut phrases: Vec<&String> = actions.keys().collect();
+        phrases.sort_by_key(|phrase| std::cmp::Reverse(phrase.len()));
+        let alternatives: Vec<String> = phrases
+            .iter()
+            .map(|phrase| {
+                phrase
+                    .split(' ')
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(r"[\s,]+")
+            })
+            .collect();
+        // Providers punctuate around spoken commands ("Thanks, comma."), which
+        // is dropped wherever the command puts punctuation of its own
+        let pattern = format!(
+            r"([\s,.;:!?]*)\b({})\b([,.;:!?]*)\s*",
+            alternatives.join("|")
+        );
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .context("Invalid dictation command")?;
+        Ok(Self {
+            regex: Some(regex),
+            actions,
+        })
+    }
+}
+
+fn normalize(phrase: &str) -> String {
+    phrase
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+impl PostProcessor for DictationCommands {
+    fn name(&self) -> &str {
+        "dictation commands"
+    }
+
+    fn process(&self, text: &str) -> String {
+        let Some(regex) = &self.regex else {
+            return text.to_string();
+        };
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for captures in regex.captures_iter(text) {
+            let (Some(whole), Some(before), Some(command), Some(after)) = (
+                captures.get(0),
+                captures.get(1),
+                captures.get(2),
+                captures.get(3),
+            ) else {
+                continue;
+            };
+            let before = before.as_str().trim();
+            result.push_str(&text[last..whole.start()]);
+            last = whole.end();
+            let Some(action) = self.actions.get(&normalize(command.as_str())) else {
+                result.push_str(whole.as_str());
+                continue;
+            };
+            debug!("Applying dictation command {:?}", command.as_str());
+            match action {
+                DictationAction::Punctuation(mark) => {
+                    result.push_str(mark);
+                    // A closing quote or bracket still ends where the provider put a period
+                    if !mark.contains([',', '.', ';', ':', '!', '?']) {
+                        result.push_str(after.as_str());
+                    }
+                    result.push(' ');
+                }
+                DictationAction::Opening(mark) => {
+                    result.push_str(before);
+                    if !result.is_empty() && !result.ends_with('\n') {
+                        result.push(' ');
+                    }
+                    result.push_str(mark);
+                }
+                DictationAction::NewLine => {
+                    result.push_str(before);
+                    result.push('\n');
+                }
+                DictationAction::NewParagraph => {
+                    result.push_str(before);
+                    result.push_str("\n\n");
+                }
+                DictationAction::ScratchThat => {
+                    let kept = result
+                        .trim_end()
+                        .trim_end_matches(['.', '!', '?'])
+                        .rfind(['.', '!', '?', '\n'])
+                        .map_or(0, |end| end + 1);
+                    result.truncate(kept);
+                    if !result.is_empty() && !result.ends_with('\n') {
+                        result.push(' ');
+                    }
+                }
+            }
+        }
+        result.push_str(&text[last..]);
+        result
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(language: &str, text: &str) -> String {
+        DictationCommands::new(language, &BTreeMap::new())
+            .unwrap()
+            .process(text)
+    }
+
+    #[test]
+    fn spoken_punctuation_replaces_provider_punctuation() {
+        assert_eq!(
+            process("en", "Thanks, comma. See you tomorrow, period."),
+            "Thanks, See you tomorrow."
+        );
+        assert_eq!(process("en", "Is it done question mark"), "Is it done?");
+    }
+
+    #[test]
+    fn quotes_and_brackets_attach_to_their_words() {
+        assert_eq!(
+            process("en", "He said open quote hello close quote."),
+            "He said \"hello\"."
+        );
+        assert_eq!(
+            process("en", "A note open bracket short close bracket here"),
+            "A note (short) here"
+        );
+    }
+
+    #[test]
+    fn line_breaks() {
+        assert_eq!(
+            process("en", "Dear Anna, new paragraph. Thanks. New line. Bob"),
+            "Dear Anna,\n\nThanks.\nBob"
+        );
+    }
+
+    #[test]
+    fn scratch_that_drops_the_previous_sentence() {
+        assert_eq!(
+            process("en", "Call me today. No, tomorrow. Scratch that. Bye."),
+            "Call me today. Bye."
+        );
+    }
+
+    #[test]
+    fn commands_follow_the_language() {
+        assert_eq!(
+            process("german", "Hallo Komma wie geht's Fragezeichen"),
+            "Hallo, wie geht's?"
+        );
+        assert_eq!(process("de", "a comma b"), "a comma b");
+        assert_eq!(process("fr", "Bonjour point d'interrogation"), "Bonjour ?");
+    }
+
+    #[test]
+    fn unknown_language_leaves_text_alone() {
+        assert_eq!(process("ja", "hello, comma"), "hello, comma");
+    }
+
+    #[test]
+    fn overrides_add_phrases() {
+        let overrides = BTreeMap::from([(
+            "Smiley  Face".to_string(),
+            DictationAction::Punctuation(" 🙂".to_string()),
+        )]);
+        let commands = DictationCommands::new("en", &overrides).unwrap();
+        assert_eq!(commands.process("See you smiley, face."), "See you 🙂.");
+    }
+}
@@ -0,0 +1,73 @@
+use super::PostProcessor;
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+/// Removes hesitation words together with the commas around them, so
+/// "I, um, think" becomes "I think".
+pub struct FillerRemover {
+    regex: Option<Regex>,
+}
+
+impl FillerRemover {
+    pub fn new(words: &[String]) -> Result<Self> {
+        let words: Vec<String> = words
+            .iter()
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty())
+            .map(regex::escape)
+            .collect();
+        if words.is_empty() {
+            return Ok(Self { regex: None });
+        }
+        let pattern = format!(r"(?:,\s*)?\b(?:{})\b(?:\s*,)?", words.join("|"));
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .context("Invalid filler words")?;
+        Ok(Self { regex: Some(regex) })
+    }
+}
+
+impl PostProcessor for FillerRemover {
+    fn name(&self) -> &str {
+        "remove fillers"
+    }
+
+    fn process(&self, text: &str) -> String {
+        match &self.regex {
+            Some(regex) => regex.replace_all(text, "").into_owned(),
+            None => text.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remover(words: &[&str]) -> FillerRemover {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        FillerRemover::new(&words).unwrap()
+    }
+
+    #[test]
+    fn removes_fillers_with_their_commas() {
+        let remover = remover(&["um", "uh"]);
+        assert_eq!(remover.process("I, um, think so"), "I think so");
+        // Leftover spaces are the whitespace step's job
+        assert_eq!(remover.process("Uh, yes"), " yes");
+    }
+
+    #[test]
+    fn keeps_words_that_contain_a_filler() {
+        assert_eq!(
+            remover(&["um"]).process("the album is umbral"),
+            "the album is umbral"
+        );
+    }
+
+    #[test]
+    fn empty_list_changes_nothing() {
+        assert_eq!(remover(&[" ", ""]).process("I, um, think"), "I, um, think");
+    }
+}
@@ -0,0 +1,102 @@
+use super::PostProcessor;
+use regex::Regex;
+
+/// Collapses runs of spaces, drops spaces before punctuation and trims lines.
+pub struct WhitespaceFixer {
+    repeated_spaces: Regex,
+    space_before_punctuation: Regex,
+}
+
+impl WhitespaceFixer {
+    pub fn new() -> Self {
+        Self {
+            repeated_spaces: Regex::new(r"[ \t]{2,}").unwrap(),
+            space_before_punctuation: Regex::new(r"[ \t]+([,.!?;:])").unwrap(),
+        }
+    }
+}
+
+impl Default for WhitespaceFixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PostProcessor for WhitespaceFixer {
+    fn name(&self) -> &str {
+        "whitespace"
+    }
+
+    fn process(&self, text: &str) -> String {
+        let text = self.repeated_spaces.replace_all(text, " ");
+        let text = self.space_before_punctuation.replace_all(&text, "$1");
+        text.lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+/// Capitalizes the first letter of the text and of every sentence after it.
+pub struct SentenceCase;
+
+impl PostProcessor for SentenceCase {
+    fn name(&self) -> &str {
+        "sentence case"
+    }
+
+    fn process(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut sentence_start = true;
+        // A terminator only ends the sentence when whitespace follows, so
+        // "example.com" and "3.5" stay as they are
+        let mut after_terminator = false;
+        for c in text.chars() {
+            if sentence_start && c.is_alphabetic() {
+                result.extend(c.to_uppercase());
+                sentence_start = false;
+                continue;
+            }
+            if c == '\n' || (after_terminator && c.is_whitespace()) {
+                sentence_start = true;
+            } else if !c.is_whitespace() && !matches!(c, '"' | '\'' | '(' | '«') {
+                sentence_start = false;
+            }
+            after_terminator = matches!(c, '.' | '!' | '?');
+            result.push(c);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_fixer_collapses_spaces_and_trims_lines() {
+        let fixer = WhitespaceFixer::new();
+        assert_eq!(
+            fixer.process("  one   two , three .\n  four  "),
+            "one two, three.\nfour"
+        );
+    }
+
+    #[test]
+    fn sentence_case_capitalizes_every_sentence() {
+        assert_eq!(
+            SentenceCase.process("hello. how are you? \"fine\", thanks!\nbye"),
+            "Hello. How are you? \"Fine\", thanks!\nBye"
+        );
+    }
+
+    #[test]
+    fn sentence_case_ignores_dots_inside_words() {
+        assert_eq!(
+            SentenceCase.process("see example.com for version 3.5"),
+            "See example.com for version 3.5"
+        );
+    }
+}
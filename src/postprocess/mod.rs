@@ -0,0 +1,196 @@
+use anyhow::Result;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+mod commands;
+mod fillers;
+mod format;
+mod rewrite;
+mod rules;
+mod snippets;
+
+pub use commands::{builtin_commands, DictationAction, DictationCommands};
+pub use fillers::FillerRemover;
+pub use format::{SentenceCase, WhitespaceFixer};
+pub use rewrite::RewritePreset;
+pub use rules::RegexRule;
+pub use snippets::SnippetExpander;
+
+/// One step of the text pipeline. Each step is a pure function of its input,
+/// so it can be built and run on its own.
+pub trait PostProcessor: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn process(&self, text: &str) -> String;
+}
+
+/// A post-processing step as written in the configuration file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostProcessStep {
+    /// Regular expression replacement; `replacement` may refer to groups as `$1`
+    Replace {
+        pattern: String,
+        replacement: String,
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    /// Drops hesitation words such as "um" and "uh", listed per language code
+    /// because they are real words elsewhere ("um" in German)
+    RemoveFillers {
+        #[serde(default = "default_fillers")]
+        words: BTreeMap<String, Vec<String>>,
+    },
+    /// Collapses repeated spaces and removes spaces before punctuation
+    Whitespace,
+    /// Capitalizes the first letter of every sentence
+    SentenceCase,
+    /// Replaces spoken trigger phrases with stored text
+    Snippets { snippets: BTreeMap<String, String> },
+    /// Interprets spoken commands such as "comma" or "new paragraph" in the
+    /// transcript's language; `commands` adds phrases per language code
+    DictationCommands {
+        #[serde(default)]
+        commands: BTreeMap<String, BTreeMap<String, DictationAction>>,
+    },
+}
+
+pub fn default_fillers() -> BTreeMap<String, Vec<String>> {
+    [
+        ("en", &["um", "umm", "uh", "uhm", "erm", "hmm"][..]),
+        ("de", &["äh", "ähm", "öhm", "hm"][..]),
+        ("fr", &["euh", "heu"][..]),
+    ]
+    .iter()
+    .map(|(language, words)| {
+        (
+            language.to_string(),
+            words.iter().map(|word| word.to_string()).collect(),
+        )
+    })
+    .collect()
+}
+
+pub fn default_steps() -> Vec<PostProcessStep> {
+    vec![
+        PostProcessStep::RemoveFillers {
+            words: default_fillers(),
+        },
+        PostProcessStep::Whitespace,
+        PostProcessStep::SentenceCase,
+    ]
+}
+
+/// Language assumed when the transcript does not report one.
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// Providers report either a code ("en") or a name ("english").
+fn language_code(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    match language.as_str() {
+        "english" => "en",
+        "german" => "de",
+        "french" => "fr",
+        "spanish" => "es",
+        code => code,
+    }
+    .to_string()
+}
+
+impl PostProcessStep {
+    /// Builds the step for text in `language`, an ISO-639-1 code.
+    pub fn build(&self, language: Option<&str>) -> Result<Box<dyn PostProcessor>> {
+        Ok(match self {
+            PostProcessStep::Replace {
+                pattern,
+                replacement,
+                case_insensitive,
+            } => Box::new(RegexRule::new(pattern, replacement, *case_insensitive)?),
+            PostProcessStep::RemoveFillers { words } => {
+                let language = language_code(language.unwrap_or(DEFAULT_LANGUAGE));
+                let words = words.get(&language).cloned().unwrap_or_default();
+                Box::new(FillerRemover::new(&words)?)
+            }
+            PostProcessStep::Whitespace => Box::new(WhitespaceFixer::new()),
+            PostProcessStep::SentenceCase => Box::new(SentenceCase),
+            PostProcessStep::Snippets { snippets } => Box::new(SnippetExpander::new(snippets)?),
+            PostProcessStep::DictationCommands { commands } => {
+                let language = language_code(language.unwrap_or(DEFAULT_LANGUAGE));
+                let overrides = commands.get(&language).cloned().unwrap_or_default();
+                Box::new(DictationCommands::new(&language, &overrides)?)
+            }
+        })
+    }
+}
+
+/// Post-processors applied in order to every transcript before it is stored.
+#[derive(Default)]
+pub struct Pipeline {
+    processors: Vec<Box<dyn PostProcessor>>,
+}
+
+impl Pipeline {
+    pub fn new(processors: Vec<Box<dyn PostProcessor>>) -> Self {
+        Self { processors }
+    }
+
+    /// Builds the configured steps; fails on the first invalid one.
+    pub fn from_steps(steps: &[PostProcessStep], language: Option<&str>) -> Result<Self> {
+        let processors = steps
+            .iter()
+            .map(|step| step.build(language))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(processors))
+    }
+
+    pub fn run(&self, text: &str) -> String {
+        self.processors
+            .iter()
+            .fold(text.to_string(), |text, processor| {
+                let processed = processor.process(&text);
+                if processed != text {
+                    debug!("Post-processor '{}' changed the text", processor.name());
+                }
+                processed
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_defaults(text: &str, language: Option<&str>) -> String {
+        Pipeline::from_steps(&default_steps(), language)
+            .unwrap()
+            .run(text)
+    }
+
+    #[test]
+    fn default_pipeline_cleans_english() {
+        assert_eq!(
+            run_defaults("so, um, we meet at  two .", None),
+            "So we meet at two."
+        );
+    }
+
+    #[test]
+    fn fillers_are_removed_per_language() {
+        assert_eq!(
+            run_defaults("ich komme um drei, äh, oder um vier", Some("german")),
+            "Ich komme um drei oder um vier"
+        );
+        assert_eq!(run_defaults("um, hola", Some("es")), "Um, hola");
+    }
+
+    #[test]
+    fn invalid_step_fails_the_pipeline() {
+        let steps = [PostProcessStep::Replace {
+            pattern: "[".to_string(),
+            replacement: String::new(),
+            case_insensitive: false,
+        }];
+        assert!(Pipeline::from_steps(&steps, None).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Instructions given to the chat model when the transcript is rewritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RewritePreset {
+    #[default]
+    FixGrammar,
+    BulletPoints,
+    FormalEmail,
+}
+
+impl RewritePreset {
+    /// Every preset, in the order the picker shows them.
+    pub const ALL: [RewritePreset; 3] = [
+        RewritePreset::FixGrammar,
+        RewritePreset::BulletPoints,
+        RewritePreset::FormalEmail,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RewritePreset::FixGrammar => "Fix grammar",
+            RewritePreset::BulletPoints => "Make bullet points",
+            RewritePreset::FormalEmail => "Formal email",
+        }
+    }
+
+    /// System prompt sent ahead of the transcript.
+    pub fn instructions(&self) -> &'static str {
+        match self {
+            RewritePreset::FixGrammar => {
+                "You clean up dictated text. Fix grammar, spelling and punctuation, and remove \
+                 repetitions and false starts. Keep the wording, meaning and language of the \
+                 original. Reply with the corrected text only."
+            }
+            RewritePreset::BulletPoints => {
+                "You turn dictated text into a concise list of bullet points, one idea per \
+                 bullet, starting each line with \"- \". Keep the language of the original and \
+                 do not add information. Reply with the list only."
+            }
+            RewritePreset::FormalEmail => {
+                "You turn dictated notes into a polite, formal email with a greeting, a clear \
+                 body and a closing. Keep the language of the original and do not invent facts, \
+                 names or dates. Reply with the email only."
+            }
+        }
+    }
+}
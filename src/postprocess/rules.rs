@@ -0,0 +1,61 @@
+use super::PostProcessor;
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+/// A user-defined regular expression replacement.
+pub struct RegexRule {
+    name: String,
+    regex: Regex,
+    replacement: String,
+}
+
+impl RegexRule {
+    pub fn new(pattern: &str, replacement: &str, case_insensitive: bool) -> Result<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("Invalid replacement pattern {:?}", pattern))?;
+        Ok(Self {
+            name: format!("replace {:?}", pattern),
+            regex,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+impl PostProcessor for RegexRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, text: &str) -> String {
+        self.regex
+            .replace_all(text, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_with_groups() {
+        let rule = RegexRule::new(r"(\d+) percent", "$1%", false).unwrap();
+        assert_eq!(
+            rule.process("up 5 percent, not 5 Percent"),
+            "up 5%, not 5 Percent"
+        );
+    }
+
+    #[test]
+    fn case_insensitive_matches_any_case() {
+        let rule = RegexRule::new("github", "GitHub", true).unwrap();
+        assert_eq!(rule.process("on Github and GITHUB"), "on GitHub and GitHub");
+    }
+
+    #[test]
+    fn rejects_invalid_pattern() {
+        assert!(RegexRule::new("(unclosed", "", false).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use super::PostProcessor;
+use anyhow::{Context, Result};
+use regex::{NoExpand, Regex, RegexBuilder};
+use std::collections::BTreeMap;
+
+/// Expands spoken trigger phrases ("my address") into stored text.
+pub struct SnippetExpander {
+    snippets: Vec<(Regex, String)>,
+}
+
+impl SnippetExpander {
+    pub fn new(snippets: &BTreeMap<String, String>) -> Result<Self> {
+        // Longer triggers first, so "my work email" wins over "my work"
+        let mut triggers: Vec<_> = snippets.iter().collect();
+        triggers.sort_by_key(|(trigger, _)| std::cmp::Reverse(trigger.len()));
+
+        let snippets = triggers
+            .into_iter()
+            .filter(|(trigger, _)| !trigger.trim().is_empty())
+            .map(|(trigger, expansion)| {
+                let pattern = format!(r"\b{}\b", regex::escape(trigger.trim()));
+                let regex = RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid snippet trigger {:?}", trigger))?;
+                Ok((regex, expansion.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { snippets })
+    }
+}
+
+impl PostProcessor for SnippetExpander {
+    fn name(&self) -> &str {
+        "snippets"
+    }
+
+    fn process(&self, text: &str) -> String {
+        self.snippets
+            .iter()
+            .fold(text.to_string(), |text, (regex, expansion)| {
+                regex
+                    .replace_all(&text, NoExpand(expansion.as_str()))
+                    .into_owned()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expander(snippets: &[(&str, &str)]) -> SnippetExpander {
+        let snippets = snippets
+            .iter()
+            .map(|(trigger, expansion)| (trigger.to_string(), expansion.to_string()))
+            .collect();
+        SnippetExpander::new(&snippets).unwrap()
+    }
+
+    #[test]
+    fn longest_trigger_wins() {
+        let expander = expander(&[("my work", "ACME"), ("my work email", "me@acme.test")]);
+        assert_eq!(
+            expander.process("Send it to My Work Email at my work"),
+            "Send it to me@acme.test at ACME"
+        );
+    }
+
+    #[test]
+    fn expansion_is_inserted_literally() {
+        let expander = expander(&[("price", "$1 each")]);
+        assert_eq!(expander.process("the price"), "the $1 each");
+    }
+
+    #[test]
+    fn triggers_match_whole_words_only() {
+        let expander = expander(&[("sig", "Best regards")]);
+        assert_eq!(expander.process("a signal"), "a signal");
+    }
+}
@@ -1,30 +1,63 @@
+use crate::audio::InputLevel;
 use crate::config::Config;
+use crate::postprocess::{Pipeline, RewritePreset};
+use crate::transcription::{
+    build_http_client, create_backend, load_cached_models, store_cached_models, transcribe_chunked,
+    AudioInput, BackendKind, HallucinationDetector, LiveTranscript, OpenAiCompatibleBackend,
+    RetryPolicy, TranscribeError, Transcript, TranscriptionMode, TranscriptionOptions,
+    TranscriptionStatus, UtteranceSplitter, LIVE_POLL_INTERVAL,
+};
+use crate::usage::{UsageLog, UsageStats};
 use anyhow::Result;
-use hound;
 use log::{debug, error, info, warn};
-use reqwest;
-use serde_json;
-use std::io::Cursor;
+use std::ops::Range;
 use std::sync::{Arc, Mutex};
-use tokio::sync::broadcast;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::{JoinError, JoinSet};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppStateEnum {
     Initial,
     Recording,
     Recorded,
+    Transcribing,
     Transcribed,
     Playing,
 }
 
+/// An in-flight transcription, cancelled through its broadcast channel the
+/// same way a recording is stopped.
+#[derive(Debug, Clone)]
+pub struct TranscriptionJob {
+    id: u64,
+    /// Value of `AppState::audio_revision` when the job started
+    audio_revision: u64,
+    cancel_sender: broadcast::Sender<()>,
+}
+
+impl TranscriptionJob {
+    pub fn cancel(&self) {
+        let _ = self.cancel_sender.send(());
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     state: AppStateEnum,
     is_recording: bool,
     transcribed_text: String,
+    transcript: Option<Transcript>,
+    /// Chat model rewrite of `transcribed_text`, shown next to it
+    rewritten_text: Option<String>,
     api_key: Option<String>,
     audio_data: Vec<f32>,
+    /// Bumped whenever `audio_data` changes, to tell recordings apart
+    audio_revision: u64,
+    transcription_job: Option<TranscriptionJob>,
+    next_job_id: u64,
     recording_stop_sender: Option<broadcast::Sender<()>>,
+    available_models: Vec<String>,
     config: Config,
 }
 
@@ -34,9 +67,15 @@ impl AppState {
             state: AppStateEnum::Initial,
             is_recording: false,
             transcribed_text: String::new(),
+            transcript: None,
+            rewritten_text: None,
             api_key: config.api_key.clone(),
             audio_data: Vec::new(),
+            audio_revision: 0,
+            transcription_job: None,
+            next_job_id: 0,
             recording_stop_sender: None,
+            available_models: load_cached_models(&config.api_base_url()),
             config,
         }
     }
@@ -45,20 +84,76 @@ impl AppState {
 #[derive(Clone)]
 pub struct StateManager {
     state: Arc<Mutex<AppState>>,
+    status_sender: broadcast::Sender<TranscriptionStatus>,
+    /// Latest microphone level while recording
+    level_sender: watch::Sender<InputLevel>,
+    /// Shared by every provider call so connections are pooled; the error
+    /// when the proxy or certificate settings are invalid
+    http_client: Result<reqwest::Client, TranscribeError>,
 }
 
 impl StateManager {
     pub fn new(config: Config) -> Self {
+        let (status_sender, _) = broadcast::channel(16);
+        let (level_sender, _) = watch::channel(InputLevel::default());
+        // Falling back to a default client would silently bypass the proxy
+        let http_client = build_http_client(&config).map_err(|e| {
+            error!("Invalid network settings: {:#}", e);
+            TranscribeError::InvalidNetworkSettings(format!("{:#}", e))
+        });
         Self {
             state: Arc::new(Mutex::new(AppState::new(config))),
+            status_sender,
+            level_sender,
+            http_client,
         }
     }
 
+    /// Receives retry and rate-limit notices from in-flight API calls.
+    pub fn subscribe_status(&self) -> broadcast::Receiver<TranscriptionStatus> {
+        self.status_sender.subscribe()
+    }
+
+    /// Receives the microphone level about 20 times a second while recording.
+    pub fn subscribe_level(&self) -> watch::Receiver<InputLevel> {
+        self.level_sender.subscribe()
+    }
+
+    pub fn publish_level(&self, level: InputLevel) {
+        self.level_sender.send_replace(level);
+    }
+
+    /// The shared HTTP client, or why it could not be built.
+    pub fn http_client(&self) -> Result<&reqwest::Client, TranscribeError> {
+        self.http_client.as_ref().map_err(Clone::clone)
+    }
+
+    fn provider(
+        &self,
+        config: &Config,
+        api_key: Option<String>,
+    ) -> Result<OpenAiCompatibleBackend, TranscribeError> {
+        Ok(OpenAiCompatibleBackend::new(
+            self.http_client()?.clone(),
+            config.backend.display_name(),
+            &config.api_base_url(),
+            api_key,
+        )
+        .with_retry_policy(RetryPolicy::from_config(config))
+        .with_status_sender(self.status_sender.clone()))
+    }
+
     // Update the get_api_key method
     pub fn get_api_key(&self) -> Option<String> {
         self.state.lock().unwrap().api_key.clone()
     }
 
+    /// Whether the configured provider needs an API key and none is set.
+    pub fn is_api_key_missing(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.config.backend == BackendKind::Groq && state.api_key.is_none()
+    }
+
     // Update the set_api_key method
     pub fn set_api_key(&self, key: Option<String>) {
         self.state.lock().unwrap().api_key = key;
@@ -80,23 +175,149 @@ impl StateManager {
         self.state.lock().unwrap().transcribed_text = text;
     }
 
+    pub fn get_transcript(&self) -> Option<Transcript> {
+        self.state.lock().unwrap().transcript.clone()
+    }
+
+    /// Stores a finished transcript along with its post-processed text.
+    pub fn set_transcript(&self, mut transcript: Transcript) {
+        transcript.text = self.post_process(&transcript);
+        let mut state = self.state.lock().unwrap();
+        state.transcribed_text = transcript.text.clone();
+        state.transcript = Some(transcript);
+        state.rewritten_text = None;
+    }
+
+    /// Runs the configured post-processing pipeline over provider output.
+    fn post_process(&self, transcript: &Transcript) -> String {
+        let config = self.get_config();
+        let options = TranscriptionOptions::from_config(&config);
+        // Translations always come back in English
+        let language = match options.mode {
+            TranscriptionMode::Translate => Some("en".to_string()),
+            TranscriptionMode::Transcribe => transcript.language.clone().or(options.language),
+        };
+        let text = &transcript.text;
+        match Pipeline::from_steps(&config.post_processing, language.as_deref()) {
+            Ok(pipeline) => pipeline.run(text),
+            Err(e) => {
+                error!(
+                    "Invalid post_processing configuration, keeping raw text: {:#}",
+                    e
+                );
+                text.to_string()
+            }
+        }
+    }
+
+    pub fn clear_transcript(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.transcribed_text.clear();
+        state.transcript = None;
+        state.rewritten_text = None;
+    }
+
+    pub fn get_rewritten_text(&self) -> Option<String> {
+        self.state.lock().unwrap().rewritten_text.clone()
+    }
+
+    /// Asks the chat model on the configured API to rewrite the transcript.
+    pub async fn rewrite_transcript(
+        &self,
+        preset: RewritePreset,
+    ) -> Result<String, TranscribeError> {
+        if self.is_api_key_missing() {
+            return Err(TranscribeError::NoApiKey);
+        }
+        let config = self.get_config();
+        let api_key = self.get_api_key();
+        let text = self.get_transcribed_text();
+        info!(
+            "Rewriting transcript with {} ({:?})",
+            config.rewrite_model, preset
+        );
+        let rewritten = self
+            .provider(&config, api_key)?
+            .complete_chat(&config.rewrite_model, preset.instructions(), &text)
+            .await?;
+        self.state.lock().unwrap().rewritten_text = Some(rewritten.clone());
+        Ok(rewritten)
+    }
+
+    pub fn get_detected_language(&self) -> Option<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .transcript
+            .as_ref()
+            .and_then(|transcript| transcript.language.clone())
+    }
+
+    /// The transcribed text with the byte ranges of it the provider was unsure
+    /// about, read together so the ranges always fit the text.
+    pub fn get_text_with_low_confidence_spans(&self) -> (String, Vec<Range<usize>>) {
+        let state = self.state.lock().unwrap();
+        let spans = state
+            .transcript
+            .as_ref()
+            // Live partial text replaces the plain text only
+            .filter(|transcript| transcript.text == state.transcribed_text)
+            .map(|transcript| {
+                transcript.low_confidence_spans(
+                    state.config.low_confidence_logprob,
+                    state.config.low_confidence_no_speech_prob,
+                )
+            })
+            .unwrap_or_default();
+        (state.transcribed_text.clone(), spans)
+    }
+
+    /// Forgets the previous transcript's segments and rewrite once new audio
+    /// is recorded; the text stays visible until it is replaced.
+    fn discard_transcript(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.transcript = None;
+        state.rewritten_text = None;
+    }
+
     pub fn get_audio_data(&self) -> Vec<f32> {
         self.state.lock().unwrap().audio_data.clone()
     }
 
+    /// Samples recorded after the first `start`, for reading a recording as it grows.
+    pub fn get_audio_data_from(&self, start: usize) -> Vec<f32> {
+        let state = self.state.lock().unwrap();
+        state
+            .audio_data
+            .get(start..)
+            .map(<[f32]>::to_vec)
+            .unwrap_or_default()
+    }
+
     pub fn set_audio_data(&self, data: Vec<f32>) {
-        self.state.lock().unwrap().audio_data = data;
+        let mut state = self.state.lock().unwrap();
+        state.audio_data = data;
+        state.audio_revision += 1;
     }
 
     pub fn clear_audio_data(&self) {
-        self.state.lock().unwrap().audio_data.clear();
+        let mut state = self.state.lock().unwrap();
+        state.audio_data.clear();
+        state.audio_revision += 1;
     }
 
+    /// Starts a new recording in place of the previous one, which live
+    /// transcription would otherwise split and send again. Returns the
+    /// receiver the recorder stops on.
     pub fn start_recording(&self) -> broadcast::Receiver<()> {
+        self.discard_transcript();
         let mut state = self.state.lock().unwrap();
+        state.audio_data.clear();
+        state.audio_revision += 1;
         let (tx, rx) = broadcast::channel(1);
         state.recording_stop_sender = Some(tx);
         state.is_recording = true;
+        state.state = AppStateEnum::Recording;
         rx
     }
 
@@ -106,137 +327,507 @@ impl StateManager {
             let _ = sender.send(());
         }
         state.is_recording = false;
+        // The recorder and live transcription run until the state changes
+        if state.state == AppStateEnum::Recording {
+            state.state = AppStateEnum::Recorded;
+        }
     }
 
     pub fn get_config(&self) -> Config {
         self.state.lock().unwrap().config.clone()
     }
 
+    pub fn get_available_models(&self) -> Vec<String> {
+        self.state.lock().unwrap().available_models.clone()
+    }
+
+    /// Fetches the provider's speech models and refreshes the on-disk cache.
+    pub async fn refresh_models(&self) -> Result<Vec<String>, TranscribeError> {
+        if self.is_api_key_missing() {
+            return Err(TranscribeError::NoApiKey);
+        }
+        let config = self.get_config();
+        let provider = self.provider(&config, self.get_api_key())?;
+        let models = provider.list_speech_models().await?;
+        info!("Caching {} speech models", models.len());
+        if let Err(e) = store_cached_models(&config.api_base_url(), &models) {
+            warn!("Failed to update model cache: {}", e);
+        }
+        self.state.lock().unwrap().available_models = models.clone();
+        Ok(models)
+    }
+
+    /// Applies `update` to the configuration and persists it if anything changed.
+    fn update_config(&self, update: impl FnOnce(&mut Config)) -> Result<()> {
+        let config = {
+            let mut state = self.state.lock().unwrap();
+            let previous = serde_json::to_value(&state.config)?;
+            update(&mut state.config);
+            if serde_json::to_value(&state.config)? == previous {
+                return Ok(());
+            }
+            state.config.clone()
+        };
+        config.save()
+    }
+
+    pub fn set_model(&self, model: &str) -> Result<()> {
+        info!("Transcription model set to {}", model);
+        self.update_config(|config| config.model = model.to_string())
+    }
+
+    pub fn set_language(&self, language: &str) -> Result<()> {
+        info!("Transcription language set to {}", language);
+        self.update_config(|config| config.language = language.to_string())
+    }
+
+    pub fn set_mode(&self, mode: TranscriptionMode) -> Result<()> {
+        info!("Transcription mode set to {:?}", mode);
+        self.update_config(|config| config.mode = mode)
+    }
+
+    pub fn set_input_device(&self, device_id: Option<String>) -> Result<()> {
+        info!("Input device set to {:?}", device_id);
+        self.update_config(|config| config.input_device = device_id)
+    }
+
+    pub fn set_live_transcription(&self, enabled: bool) -> Result<()> {
+        info!(
+            "Live transcription {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+        self.update_config(|config| config.live_transcription = enabled)
+    }
+
+    pub fn set_rewrite_preset(&self, preset: RewritePreset) -> Result<()> {
+        info!("Rewrite preset set to {:?}", preset);
+        self.update_config(|config| config.rewrite_preset = preset)
+    }
+
     pub fn is_recording_sync(&self) -> bool {
         self.state.lock().unwrap().is_recording
     }
-    pub async fn validate_and_save_api_key(&self, api_key: &str) -> Result<bool> {
+    pub async fn validate_and_save_api_key(&self, api_key: &str) -> Result<(), TranscribeError> {
         debug!("Attempting to validate and save API key");
-        let is_valid = api_key.starts_with("gsk_") && api_key.len() >= 20;
-
-        if is_valid {
-            debug!("API key format is valid, attempting to validate with Groq API");
-            match self.validate_api_key_with_groq(api_key).await {
-                Ok(true) => {
-                    info!("API key validated successfully with Groq");
-                    Config::set_api_key(api_key)?;
-                    let mut state = self.state.lock().unwrap();
-                    state.api_key = Some(api_key.to_string());
-                    state.config.api_key = Some(api_key.to_string());
-                    info!("API key saved successfully");
-                    Ok(true)
-                }
-                Ok(false) => {
-                    warn!("API key format is valid but rejected by Groq API");
-                    Ok(false)
-                }
-                Err(e) => {
-                    error!("Error validating API key with Groq: {:?}", e);
-                    Ok(false)
-                }
-            }
-        } else {
+        let config = self.get_config();
+        if !config.is_valid_api_key(api_key) {
             error!("Invalid API key format");
-            Ok(false)
+            return Err(TranscribeError::InvalidApiKey);
         }
+
+        debug!("API key format is valid, attempting to validate with the provider");
+        if let Err(e) = self.validate_api_key_with_provider(&config, api_key).await {
+            warn!("API key could not be validated with the provider: {}", e);
+            return Err(e);
+        }
+
+        info!("API key validated successfully with the provider");
+        Config::set_api_key(api_key).map_err(|e| TranscribeError::Keyring(e.to_string()))?;
+        let mut state = self.state.lock().unwrap();
+        state.api_key = Some(api_key.to_string());
+        state.config.api_key = Some(api_key.to_string());
+        info!("API key saved successfully");
+        Ok(())
     }
 
-    async fn validate_api_key_with_groq(&self, api_key: &str) -> Result<bool> {
-        debug!("Sending request to Groq API to validate key");
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://api.groq.com/openai/v1/models")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
-            .await?;
+    async fn validate_api_key_with_provider(
+        &self,
+        config: &Config,
+        api_key: &str,
+    ) -> Result<(), TranscribeError> {
+        debug!(
+            "Sending request to {} to validate key",
+            config.api_base_url()
+        );
+        let provider = self.provider(config, Some(api_key.to_string()))?;
+        provider.validate_credentials().await
+    }
+
+    /// Registers a transcription job for the current recording. A job for the same
+    /// recording is rejected; one for an older recording is cancelled.
+    fn start_transcription(&self) -> Result<(u64, broadcast::Receiver<()>), TranscribeError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(job) = &state.transcription_job {
+            if job.audio_revision == state.audio_revision {
+                warn!("Transcription job {} is already running", job.id);
+                return Err(TranscribeError::AlreadyTranscribing);
+            }
+        }
+        let job = Self::register_job(&mut state);
+        state.state = AppStateEnum::Transcribing;
+        Ok(job)
+    }
+
+    /// Makes a new job the one `cancel_transcription` stops, cancelling the
+    /// previous job.
+    fn register_job(state: &mut AppState) -> (u64, broadcast::Receiver<()>) {
+        if let Some(job) = &state.transcription_job {
+            info!("Recording changed, cancelling transcription job {}", job.id);
+            job.cancel();
+        }
+
+        let (tx, rx) = broadcast::channel(1);
+        state.next_job_id += 1;
+        let id = state.next_job_id;
+        state.transcription_job = Some(TranscriptionJob {
+            id,
+            audio_revision: state.audio_revision,
+            cancel_sender: tx,
+        });
+        debug!("Started transcription job {}", id);
+        (id, rx)
+    }
 
-        let status = response.status();
-        debug!("Received response from Groq API with status: {}", status);
-        Ok(status.is_success())
+    fn finish_transcription(&self, id: u64, succeeded: bool) {
+        let mut state = self.state.lock().unwrap();
+        // A newer job may have replaced this one
+        if state.transcription_job.as_ref().map(|job| job.id) != Some(id) {
+            return;
+        }
+        state.transcription_job = None;
+        if state.state == AppStateEnum::Transcribing {
+            state.state = if succeeded {
+                AppStateEnum::Transcribed
+            } else {
+                AppStateEnum::Recorded
+            };
+        }
+        debug!("Finished transcription job {}", id);
+    }
+
+    pub fn has_transcription_job(&self) -> bool {
+        self.state.lock().unwrap().transcription_job.is_some()
+    }
+
+    /// Cancels the running transcription job, if any.
+    pub fn cancel_transcription(&self) {
+        if let Some(job) = &self.state.lock().unwrap().transcription_job {
+            info!("Cancelling transcription job {}", job.id);
+            job.cancel();
+        }
     }
 
-    pub async fn transcribe_audio(&self) -> Result<String> {
-        let api_key = self
-            .get_api_key()
-            .ok_or_else(|| anyhow::anyhow!("API key not set"))?;
-        let audio_data = {
+    /// Transcribes the current recording as a cancellable job.
+    pub async fn transcribe_audio(&self) -> Result<Transcript, TranscribeError> {
+        let (id, mut cancel_receiver) = self.start_transcription()?;
+        // Dropping the request future aborts the upload and any chunk tasks
+        let result = tokio::select! {
+            result = self.run_transcription() => result,
+            _ = cancel_receiver.recv() => Err(TranscribeError::Cancelled),
+        };
+        self.finish_transcription(id, result.is_ok());
+        result
+    }
+
+    async fn run_transcription(&self) -> Result<Transcript, TranscribeError> {
+        let http_client = self.http_client()?;
+        let (config, api_key, audio_data) = {
             let state = self.state.lock().unwrap();
-            state.audio_data.clone()
+            (
+                state.config.clone(),
+                state.api_key.clone(),
+                state.audio_data.clone(),
+            )
         };
 
-        let sample_rate = 44100; // Assuming this is the sample rate we're using
+        let recorded = AudioInput {
+            samples: audio_data,
+            sample_rate: config.sample_rate,
+            channels: config.channels,
+        };
 
         debug!("Starting transcription process...");
         debug!(
             "Audio data length: {} samples ({:.2} seconds)",
-            audio_data.len(),
-            audio_data.len() as f32 / sample_rate as f32
+            recorded.samples.len(),
+            recorded.duration_secs()
         );
 
-        // WAV encoding
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
+        // Resampling a long recording takes a moment, so keep it off the runtime
+        let audio = tokio::task::spawn_blocking(move || recorded.to_whisper_format())
+            .await
+            .map_err(|e| TranscribeError::AudioEncoding(e.to_string()))?;
+
+        let options = TranscriptionOptions::from_config(&config);
+        info!(
+            "Transcribing with mode: {:?}, language: {:?}",
+            options.mode, options.language
+        );
+
+        let mut providers = vec![(config.primary_provider(), api_key.clone())];
+        for provider in &config.fallback_providers {
+            providers.push((
+                provider.clone(),
+                provider.fallback_api_key(api_key.as_deref()),
+            ));
+        }
+
+        let mut first_error = None;
+        for (index, (provider, provider_key)) in providers.iter().enumerate() {
+            if index > 0 {
+                let failed = providers[index - 1].0.display_name();
+                info!(
+                    "Falling back from {} to {}",
+                    failed,
+                    provider.display_name()
+                );
+                let _ = self.status_sender.send(TranscriptionStatus::FallingBack {
+                    failed: failed.to_string(),
+                    next: provider.display_name().to_string(),
+                });
+            }
+
+            // Quotas only cover audio sent to providers
+            if provider.backend != BackendKind::Local {
+                if let Err(e) = self.check_quota(&config, audio.duration_secs()) {
+                    warn!("Not sending audio to {}: {}", provider.display_name(), e);
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            }
+
+            // A provider that is not set up is skipped like one that is down
+            let backend = match create_backend(
+                &config,
+                provider,
+                provider_key.clone(),
+                http_client,
+                &self.status_sender,
+            ) {
+                Ok(backend) => backend,
+                Err(e) => {
+                    warn!("{} is not available: {}", provider.display_name(), e);
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            };
+
+            info!("Transcribing with {} backend", backend.name());
+            let result = if backend.supports_long_audio() {
+                backend.transcribe(&audio, &options).await
+            } else {
+                let max_chunk_secs = config.max_chunk_secs();
+                transcribe_chunked(Arc::clone(&backend), &audio, &options, max_chunk_secs).await
+            };
+            match result {
+                Ok(mut transcript) => {
+                    HallucinationDetector::from_config(&config).apply(&mut transcript, &audio);
+                    transcript.provider = Some(backend.name().to_string());
+                    return Ok(transcript);
+                }
+                Err(e) if e.is_retryable() => {
+                    warn!("{} failed: {}", backend.name(), e);
+                    first_error.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        // Report the primary provider's failure rather than the last fallback's
+        Err(first_error.expect("the provider chain always contains the primary provider"))
+    }
+
+    /// Daily and monthly totals of the audio sent to providers.
+    pub fn usage_stats(&self) -> Result<UsageStats> {
+        UsageLog::default().stats()
+    }
+
+    /// Refuses an upload of `audio_secs` that would exceed the hard quota and
+    /// warns once the soft quota is passed.
+    fn check_quota(&self, config: &Config, audio_secs: f64) -> Result<(), TranscribeError> {
+        if config.soft_quota_minutes.is_none() && config.hard_quota_minutes.is_none() {
+            return Ok(());
+        }
+        let used_minutes = match self.usage_stats() {
+            Ok(stats) => stats.this_month().audio_minutes() + audio_secs / 60.0,
+            Err(e) => {
+                warn!("Could not read usage log, skipping quota check: {:#}", e);
+                return Ok(());
+            }
         };
-        let mut wav_buffer = Cursor::new(Vec::new());
-        let mut wav_writer = hound::WavWriter::new(&mut wav_buffer, spec)?;
-        for &sample in &audio_data {
-            wav_writer.write_sample((sample * 32767.0) as i16)?;
+
+        if let Some(quota_minutes) = config.hard_quota_minutes {
+            if used_minutes > quota_minutes {
+                return Err(TranscribeError::QuotaExceeded {
+                    used_minutes,
+                    quota_minutes,
+                });
+            }
         }
-        wav_writer.finalize()?;
-        let wav_data = wav_buffer.into_inner();
-
-        let file_part = reqwest::multipart::Part::bytes(wav_data)
-            .file_name("audio.wav")
-            .mime_str("audio/wav")?;
-
-        let form = reqwest::multipart::Form::new()
-            .part("file", file_part)
-            .text("model", "distil-whisper-large-v3-en")
-            .text("temperature", "0")
-            .text("response_format", "json")
-            .text("language", "en");
-
-        debug!("Sending WAV file to Groq API...");
-        let client = reqwest::Client::new();
-        let response = client
-            .post("https://api.groq.com/openai/v1/audio/transcriptions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .multipart(form)
-            .send()
-            .await?;
+        if let Some(quota_minutes) = config.soft_quota_minutes {
+            if used_minutes > quota_minutes {
+                warn!(
+                    "Soft quota exceeded: {:.1} of {:.0} minutes",
+                    used_minutes, quota_minutes
+                );
+                let _ = self.status_sender.send(TranscriptionStatus::QuotaWarning {
+                    used_minutes,
+                    quota_minutes,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Transcribes utterances while they are being recorded, reporting the text so
+    /// far through `on_update`. Returns the final transcript once recording stops,
+    /// along with the error that stopped transcription early, if any.
+    pub async fn transcribe_live(
+        &self,
+        on_update: impl Fn(&Transcript) + Send,
+    ) -> Result<(Transcript, Option<TranscribeError>), TranscribeError> {
+        let (id, mut cancel_receiver) = Self::register_job(&mut self.state.lock().unwrap());
+        // Dropping the session aborts the utterance tasks; the recording goes on
+        let result = tokio::select! {
+            result = self.run_live_transcription(on_update) => result,
+            _ = cancel_receiver.recv() => Err(TranscribeError::Cancelled),
+        };
+        self.finish_transcription(id, result.is_ok());
+        result
+    }
+
+    async fn run_live_transcription(
+        &self,
+        on_update: impl Fn(&Transcript) + Send,
+    ) -> Result<(Transcript, Option<TranscribeError>), TranscribeError> {
+        self.discard_transcript();
+        let (config, api_key) = {
+            let state = self.state.lock().unwrap();
+            (state.config.clone(), state.api_key.clone())
+        };
+        let options = TranscriptionOptions::from_config(&config);
+        let detector = HallucinationDetector::from_config(&config);
+        // Utterances are short-lived, so live mode sticks to the primary provider
+        let backend = create_backend(
+            &config,
+            &config.primary_provider(),
+            api_key,
+            self.http_client()?,
+            &self.status_sender,
+        )?;
+        info!("Live transcription with {} backend", backend.name());
+
+        let mut splitter = UtteranceSplitter::new(
+            config.sample_rate,
+            config.channels,
+            Duration::from_millis(config.live_silence_ms),
+        );
+        let mut live = LiveTranscript::default();
+        let mut tasks = JoinSet::new();
+        let mut stopped_by = None;
+        loop {
+            let recording = self.get_app_state() == AppStateEnum::Recording;
+            let pending = self.get_audio_data_from(splitter.consumed_samples());
+            let utterances = if recording {
+                splitter.split(&pending)
+            } else {
+                splitter.finish(&pending)
+            };
+            for utterance in utterances {
+                if stopped_by.is_some() {
+                    break;
+                }
+                if config.backend != BackendKind::Local {
+                    // What was already transcribed is kept; later utterances are not sent
+                    if let Err(e) = self.check_quota(&config, utterance.audio.duration_secs()) {
+                        warn!("Live transcription stopped: {}", e);
+                        let _ = self
+                            .status_sender
+                            .send(TranscriptionStatus::UtteranceFailed {
+                                message: e.user_message(),
+                            });
+                        stopped_by = Some(e);
+                        break;
+                    }
+                }
+                let backend = Arc::clone(&backend);
+                let options = options.clone();
+                let detector = detector.clone();
+                let audio = utterance.audio.clone();
+                let index = live.push(utterance);
+                tasks.spawn(async move {
+                    let result = async {
+                        // Resampling runs the sinc filter over the whole utterance
+                        let audio = tokio::task::spawn_blocking(move || audio.to_whisper_format())
+                            .await
+                            .map_err(|e| TranscribeError::AudioEncoding(e.to_string()))?;
+                        let mut transcript = backend.transcribe(&audio, &options).await?;
+                        detector.apply(&mut transcript, &audio);
+                        Ok(transcript)
+                    }
+                    .await;
+                    (index, result)
+                });
+            }
+            if !recording {
+                break;
+            }
 
-        let status = response.status();
-        debug!("Response status: {}", status);
-
-        if status.is_success() {
-            let response_text = response.text().await?.to_string();
-            debug!("Response body: {}", response_text);
-            let json: serde_json::Value = serde_json::from_str(&response_text)?;
-            let transcribed_text = json["text"].as_str().unwrap_or("").to_string();
-            Ok(transcribed_text)
-        } else {
-            let error_text = response.text().await?;
-            error!("Error response body: {}", error_text);
-            Err(anyhow::anyhow!(
-                "API request failed: {}. Error: {}",
-                status,
-                error_text
-            ))
+            tokio::select! {
+                Some(result) = tasks.join_next() => {
+                    self.complete_utterance(&mut live, result, &on_update);
+                }
+                _ = tokio::time::sleep(LIVE_POLL_INTERVAL) => {}
+            }
+        }
+
+        debug!(
+            "Recording stopped, waiting for {} utterance(s)",
+            tasks.len()
+        );
+        while let Some(result) = tasks.join_next().await {
+            self.complete_utterance(&mut live, result, &on_update);
         }
+        if let Some(error) = live.total_failure() {
+            return Err(error);
+        }
+        let mut transcript = live.transcript();
+        transcript.provider = Some(backend.name().to_string());
+        Ok((transcript, stopped_by))
+    }
+
+    /// Adds a finished utterance task to `live`. A failed utterance is
+    /// reported and left out, so the rest of the session is still transcribed.
+    fn complete_utterance(
+        &self,
+        live: &mut LiveTranscript,
+        result: Result<(usize, Result<Transcript, TranscribeError>), JoinError>,
+        on_update: impl Fn(&Transcript),
+    ) {
+        let error = match result {
+            Ok((index, Ok(transcript))) => {
+                live.complete(index, transcript);
+                self.publish_live_transcript(live, on_update);
+                return;
+            }
+            Ok((_, Err(e))) => e,
+            Err(e) => {
+                TranscribeError::Backend(format!("Utterance transcription task failed: {}", e))
+            }
+        };
+        warn!(
+            "Skipping utterance that could not be transcribed: {}",
+            error
+        );
+        let _ = self
+            .status_sender
+            .send(TranscriptionStatus::UtteranceFailed {
+                message: error.user_message(),
+            });
+        live.fail(error);
+    }
+
+    fn publish_live_transcript(&self, live: &LiveTranscript, on_update: impl Fn(&Transcript)) {
+        let mut transcript = live.transcript();
+        transcript.text = self.post_process(&transcript);
+        self.set_transcribed_text(transcript.text.clone());
+        on_update(&transcript);
     }
 
     // Add this method to the StateManager implementation
     pub fn append_audio_data(&self, data: &[f32]) {
         let mut state = self.state.lock().unwrap();
         state.audio_data.extend_from_slice(data);
+        state.audio_revision += 1;
     }
 
     pub fn has_api_key(&self) -> bool {
@@ -245,8 +836,8 @@ impl StateManager {
             .unwrap_or(false)
     }
 
-    pub fn remove_api_key(&self) -> Result<()> {
-        Config::remove_api_key()?;
+    pub fn remove_api_key(&self) -> Result<(), TranscribeError> {
+        Config::remove_api_key().map_err(|e| TranscribeError::Keyring(e.to_string()))?;
         let mut state = self.state.lock().unwrap();
         state.api_key = None;
         state.config.api_key = None;
@@ -0,0 +1,293 @@
+use super::{AudioInput, TranscribeError, Transcript, TranscriptionBackend, TranscriptionOptions};
+use log::{debug, info};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Audio each chunk repeats from before its cut point, so the model has context
+/// for the first words it owns.
+const CHUNK_OVERLAP_SECS: f64 = 1.0;
+/// The cut point is searched for in the last quarter of each chunk.
+const SILENCE_SEARCH_FRACTION: f64 = 0.25;
+/// Energy is compared over windows of this length when looking for silence.
+const ENERGY_WINDOW_SECS: f64 = 0.02;
+/// Upper bound on simultaneous uploads, to stay clear of provider rate limits.
+const MAX_CONCURRENT_CHUNKS: usize = 4;
+
+/// A slice of a longer recording, positioned on the recording's timeline.
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    /// Start of the chunk within the recording, in seconds
+    pub offset: f64,
+    /// Segments ending at or before this time belong to the previous chunk
+    pub keep_from: f64,
+    pub audio: AudioInput,
+}
+
+/// Splits `audio` into chunks of at most `max_chunk_secs`, cutting at the
+/// quietest point near the end of each chunk.
+pub fn split_at_silence(audio: &AudioInput, max_chunk_secs: f64) -> Vec<AudioChunk> {
+    let channels = audio.channels.max(1) as usize;
+    let frames = audio.samples.len() / channels;
+    let rate = audio.sample_rate as f64;
+    let max_frames = (max_chunk_secs * rate) as usize;
+    let overlap_frames = (CHUNK_OVERLAP_SECS * rate) as usize;
+
+    if frames <= max_frames || max_frames <= overlap_frames * 2 {
+        return vec![AudioChunk {
+            offset: 0.0,
+            keep_from: 0.0,
+            audio: audio.clone(),
+        }];
+    }
+
+    let mut chunks = Vec::new();
+    // First frame the next chunk is responsible for
+    let mut owned_from = 0;
+    while owned_from < frames {
+        let start = owned_from.saturating_sub(overlap_frames);
+        let cut = if frames - start <= max_frames {
+            frames
+        } else {
+            let latest_cut = start + max_frames;
+            let search_from = latest_cut - (max_frames as f64 * SILENCE_SEARCH_FRACTION) as usize;
+            quietest_frame(audio, search_from, latest_cut)
+        };
+
+        chunks.push(AudioChunk {
+            offset: start as f64 / rate,
+            keep_from: owned_from as f64 / rate,
+            audio: AudioInput {
+                samples: audio.samples[start * channels..cut * channels].to_vec(),
+                sample_rate: audio.sample_rate,
+                channels: audio.channels,
+            },
+        });
+        owned_from = cut;
+    }
+
+    debug!(
+        "Split {:.1}s of audio into {} chunks",
+        audio.duration_secs(),
+        chunks.len()
+    );
+    chunks
+}
+
+/// Returns the frame in `from..to` at the centre of the lowest-energy window.
+fn quietest_frame(audio: &AudioInput, from: usize, to: usize) -> usize {
+    let channels = audio.channels.max(1) as usize;
+    let window = ((ENERGY_WINDOW_SECS * audio.sample_rate as f64) as usize).max(1);
+
+    let mut best_frame = to;
+    let mut best_energy = f32::INFINITY;
+    let mut frame = from;
+    while frame + window <= to {
+        let samples = &audio.samples[frame * channels..(frame + window) * channels];
+        let energy = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+        if energy < best_energy {
+            best_energy = energy;
+            best_frame = frame + window / 2;
+        }
+        frame += window;
+    }
+    best_frame
+}
+
+/// Transcribes each chunk concurrently and stitches the results into one
+/// transcript on the recording's timeline.
+pub async fn transcribe_chunked(
+    backend: Arc<dyn TranscriptionBackend>,
+    audio: &AudioInput,
+    options: &TranscriptionOptions,
+    max_chunk_secs: f64,
+) -> Result<Transcript, TranscribeError> {
+    let chunks = split_at_silence(audio, max_chunk_secs);
+    if chunks.len() == 1 {
+        return backend.transcribe(audio, options).await;
+    }
+    info!(
+        "Recording is longer than {:.0}s, transcribing {} chunks",
+        max_chunk_secs,
+        chunks.len()
+    );
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_CHUNKS));
+    let mut tasks = JoinSet::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let backend = Arc::clone(&backend);
+        let semaphore = Arc::clone(&semaphore);
+        let options = options.clone();
+        let audio = chunk.audio.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .map_err(|e| TranscribeError::Backend(e.to_string()))?;
+            debug!("Transcribing chunk {}", index);
+            let transcript = backend.transcribe(&audio, &options).await?;
+            Ok::<_, TranscribeError>((index, transcript))
+        });
+    }
+
+    let mut transcripts: Vec<Option<Transcript>> = vec![None; chunks.len()];
+    while let Some(result) = tasks.join_next().await {
+        let (index, transcript) = result.map_err(|e| {
+            TranscribeError::Backend(format!("Chunk transcription task failed: {}", e))
+        })??;
+        transcripts[index] = Some(transcript);
+    }
+
+    let parts = chunks
+        .iter()
+        .zip(transcripts)
+        .map(|(chunk, transcript)| (chunk, transcript.unwrap_or_default()))
+        .collect::<Vec<_>>();
+    Ok(stitch(&parts, audio.duration_secs()))
+}
+
+/// Merges per-chunk transcripts, shifting segment times by each chunk's offset
+/// and dropping segments the previous chunk already covered.
+pub(super) fn stitch(parts: &[(&AudioChunk, Transcript)], duration: f64) -> Transcript {
+    let mut stitched = Transcript {
+        duration: Some(duration),
+        ..Transcript::default()
+    };
+    let mut texts = Vec::new();
+
+    for (chunk, transcript) in parts {
+        if stitched.language.is_none() {
+            stitched.language = transcript.language.clone();
+        }
+
+        if transcript.segments.is_empty() {
+            // Without timings the overlap cannot be trimmed; keep the whole text
+            texts.push(transcript.text.trim().to_string());
+            continue;
+        }
+
+        for segment in &transcript.segments {
+            let mut segment = segment.clone();
+            segment.start += chunk.offset;
+            segment.end += chunk.offset;
+            if segment.end <= chunk.keep_from {
+                continue;
+            }
+            texts.push(segment.text.trim().to_string());
+            stitched.segments.push(segment);
+        }
+    }
+
+    stitched.text = texts
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    stitched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::test_util::{recording, segment, SAMPLE_RATE};
+
+    #[test]
+    fn short_audio_is_one_chunk() {
+        let chunks = split_at_silence(&recording(&[(0.5, 5.0)]), 10.0);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].audio.samples.len(), 5 * SAMPLE_RATE as usize);
+    }
+
+    #[test]
+    fn cuts_at_the_pause_and_repeats_the_overlap() {
+        // A pause around 8.5 s
+        let audio = recording(&[(0.5, 8.45), (0.0, 0.1), (0.5, 6.45)]);
+        let chunks = split_at_silence(&audio, 10.0);
+        assert_eq!(chunks.len(), 2);
+        let cut = chunks[1].keep_from;
+        assert!((cut - 8.5).abs() < 0.05, "cut at {}", cut);
+        assert_eq!(chunks[0].offset, 0.0);
+        assert_eq!(chunks[0].audio.duration_secs(), cut);
+        assert!((chunks[1].offset - (cut - CHUNK_OVERLAP_SECS)).abs() < 1e-9);
+        assert!((chunks[1].offset + chunks[1].audio.duration_secs() - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn chunks_stay_under_the_limit() {
+        let chunks = split_at_silence(&recording(&[(0.5, 60.0)]), 10.0);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.audio.duration_secs() <= 10.0));
+        for pair in chunks.windows(2) {
+            assert!(pair[1].keep_from > pair[0].keep_from);
+            assert!((pair[1].offset - (pair[1].keep_from - CHUNK_OVERLAP_SECS)).abs() < 1e-9);
+        }
+        let last = chunks.last().unwrap();
+        assert!((last.offset + last.audio.duration_secs() - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stitch_shifts_segments_and_drops_the_overlap() {
+        let chunk = |offset: f64, keep_from: f64| AudioChunk {
+            offset,
+            keep_from,
+            audio: recording(&[]),
+        };
+        let (first, second) = (chunk(0.0, 0.0), chunk(7.0, 8.0));
+        let parts = [
+            (
+                &first,
+                Transcript {
+                    text: "One two. Three.".to_string(),
+                    language: Some("en".to_string()),
+                    segments: vec![segment(0.0, 4.0, " One two."), segment(4.0, 8.0, " Three.")],
+                    ..Transcript::default()
+                },
+            ),
+            (
+                &second,
+                Transcript {
+                    text: "Three. Four.".to_string(),
+                    segments: vec![segment(0.0, 1.0, " Three."), segment(1.0, 3.0, " Four.")],
+                    ..Transcript::default()
+                },
+            ),
+        ];
+        let stitched = stitch(&parts, 10.0);
+        assert_eq!(stitched.text, "One two. Three. Four.");
+        assert_eq!(stitched.language.as_deref(), Some("en"));
+        assert_eq!(stitched.duration, Some(10.0));
+        assert_eq!(stitched.segments.len(), 3);
+        assert_eq!(
+            (stitched.segments[2].start, stitched.segments[2].end),
+            (8.0, 10.0)
+        );
+    }
+
+    #[test]
+    fn stitch_keeps_text_without_segments() {
+        let chunk = AudioChunk {
+            offset: 0.0,
+            keep_from: 0.0,
+            audio: recording(&[]),
+        };
+        let parts = [
+            (
+                &chunk,
+                Transcript {
+                    text: " Hello ".to_string(),
+                    ..Transcript::default()
+                },
+            ),
+            (&chunk, Transcript::default()),
+            (
+                &chunk,
+                Transcript {
+                    text: "world".to_string(),
+                    ..Transcript::default()
+                },
+            ),
+        ];
+        assert_eq!(stitch(&parts, 1.0).text, "Hello world");
+    }
+}
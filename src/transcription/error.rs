@@ -0,0 +1,276 @@
+use reqwest::StatusCode;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Everything that can go wrong between pressing Transcribe (or saving an API
+/// key) and getting text back, in terms the UI can act on.
+#[derive(Debug, Clone, Error)]
+pub enum TranscribeError {
+    #[error("No API key is set")]
+    NoApiKey,
+    #[error("The API key was rejected by the provider")]
+    InvalidApiKey,
+    #[error("Rate limited by the provider")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("The upload exceeds the provider's size limit")]
+    PayloadTooLarge,
+    #[error("Network unavailable: {0}")]
+    NetworkDown(String),
+    /// The proxy or certificate settings could not be applied
+    #[error("Invalid network settings: {0}")]
+    InvalidNetworkSettings(String),
+    #[error("Provider rejected the request ({status}): {message}")]
+    ProviderClientError { status: u16, message: String },
+    #[error("Provider error ({status}): {message}")]
+    ProviderServerError { status: u16, message: String },
+    #[error("Failed to encode audio: {0}")]
+    AudioEncoding(String),
+    /// Recording, playback or listing the input devices failed
+    #[error("Audio device error: {0}")]
+    AudioDevice(String),
+    #[error("Could not save settings: {0}")]
+    Settings(String),
+    /// The config file exists but could not be read, so defaults are in use
+    #[error("Could not read the configuration file: {0}")]
+    InvalidConfig(String),
+    #[error("Unexpected response from provider: {0}")]
+    InvalidResponse(String),
+    #[error("Could not access the system keyring: {0}")]
+    Keyring(String),
+    #[error("Monthly quota of {quota_minutes:.0} audio minutes exceeded")]
+    QuotaExceeded {
+        used_minutes: f64,
+        quota_minutes: f64,
+    },
+    #[error("This recording is already being transcribed")]
+    AlreadyTranscribing,
+    #[error("Transcription cancelled")]
+    Cancelled,
+    /// Configuration or runtime failures of a specific backend, e.g. the local model
+    #[error("{0}")]
+    Backend(String),
+}
+
+/// What the user can do about an error, offered as a button next to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryAction {
+    EnterApiKey,
+    Retry,
+    RecordAgain,
+}
+
+impl RecoveryAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecoveryAction::EnterApiKey => "Enter API Key",
+            RecoveryAction::Retry => "Retry",
+            RecoveryAction::RecordAgain => "Record Again",
+        }
+    }
+}
+
+impl TranscribeError {
+    /// Maps an unsuccessful HTTP response to the matching variant.
+    pub fn from_status(status: StatusCode, retry_after: Option<Duration>, body: &str) -> Self {
+        let message = body.trim().to_string();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => TranscribeError::InvalidApiKey,
+            StatusCode::PAYLOAD_TOO_LARGE => TranscribeError::PayloadTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => TranscribeError::RateLimited { retry_after },
+            status if status.is_server_error() => TranscribeError::ProviderServerError {
+                status: status.as_u16(),
+                message,
+            },
+            status => TranscribeError::ProviderClientError {
+                status: status.as_u16(),
+                message,
+            },
+        }
+    }
+
+    /// Explanation shown in the error label, including what to do next.
+    pub fn user_message(&self) -> String {
+        match self {
+            TranscribeError::NoApiKey => {
+                "No API key is set. Enter your API key to start transcribing.".to_string()
+            }
+            TranscribeError::InvalidApiKey => {
+                "The provider rejected the API key. Check that it is correct and still active."
+                    .to_string()
+            }
+            TranscribeError::RateLimited {
+                retry_after: Some(wait),
+            } => format!(
+                "The provider's rate limit was reached. Try again in {:.0} seconds.",
+                wait.as_secs_f64().ceil()
+            ),
+            TranscribeError::RateLimited { retry_after: None } => {
+                "The provider's rate limit was reached. Wait a moment and try again.".to_string()
+            }
+            TranscribeError::PayloadTooLarge => {
+                "The upload is larger than the provider accepts. Lower max_file_size_bytes in \
+                 the configuration so recordings are sent in smaller chunks."
+                    .to_string()
+            }
+            TranscribeError::NetworkDown(_) => {
+                "Could not reach the provider. Check your network connection and try again."
+                    .to_string()
+            }
+            TranscribeError::InvalidNetworkSettings(message) => format!(
+                "Invalid network settings: {}. Fix proxy_url or extra_root_certificates in the \
+                 configuration and restart.",
+                message
+            ),
+            TranscribeError::ProviderClientError { status, message } => format!(
+                "The provider rejected the request (HTTP {}). Check the model and endpoint \
+                 settings. {}",
+                status, message
+            ),
+            TranscribeError::ProviderServerError { status, .. } => format!(
+                "The provider is having problems (HTTP {}). Try again later.",
+                status
+            ),
+            TranscribeError::AudioEncoding(_) => {
+                "The recording could not be encoded for upload. Try recording again.".to_string()
+            }
+            TranscribeError::AudioDevice(message) => format!(
+                "The audio device failed: {}. Check the input device and try again.",
+                message
+            ),
+            TranscribeError::Settings(message) => format!(
+                "Could not save settings: {}. The change only lasts until the app is closed.",
+                message
+            ),
+            TranscribeError::InvalidConfig(message) => format!(
+                "Could not read the configuration file: {}. Defaults are used, and settings \
+                 are not saved until the file is fixed and the app restarted.",
+                message
+            ),
+            TranscribeError::InvalidResponse(_) => {
+                "The provider sent a response that could not be read. Try again.".to_string()
+            }
+            TranscribeError::Keyring(message) => format!(
+                "Could not access the system keyring: {}. Make sure a secret service is running.",
+                message
+            ),
+            TranscribeError::QuotaExceeded {
+                used_minutes,
+                quota_minutes,
+            } => format!(
+                "This upload would bring this month's usage to {:.1} of {:.0} allowed audio \
+                 minutes. Raise hard_quota_minutes in the configuration to continue.",
+                used_minutes, quota_minutes
+            ),
+            TranscribeError::AlreadyTranscribing => {
+                "This recording is already being transcribed. Wait for it to finish or cancel it."
+                    .to_string()
+            }
+            TranscribeError::Cancelled => "Transcription cancelled.".to_string(),
+            TranscribeError::Backend(message) => message.clone(),
+        }
+    }
+
+    /// Whether another attempt, possibly with another provider, could succeed.
+    pub fn is_retryable(&self) -> bool {
+        self.recovery_action() == Some(RecoveryAction::Retry)
+    }
+
+    pub fn recovery_action(&self) -> Option<RecoveryAction> {
+        match self {
+            TranscribeError::NoApiKey | TranscribeError::InvalidApiKey => {
+                Some(RecoveryAction::EnterApiKey)
+            }
+            TranscribeError::RateLimited { .. }
+            | TranscribeError::NetworkDown(_)
+            | TranscribeError::ProviderServerError { .. }
+            | TranscribeError::InvalidResponse(_) => Some(RecoveryAction::Retry),
+            TranscribeError::AudioEncoding(_) => Some(RecoveryAction::RecordAgain),
+            TranscribeError::PayloadTooLarge
+            | TranscribeError::InvalidNetworkSettings(_)
+            | TranscribeError::AudioDevice(_)
+            | TranscribeError::Settings(_)
+            | TranscribeError::InvalidConfig(_)
+            | TranscribeError::ProviderClientError { .. }
+            | TranscribeError::Keyring(_)
+            | TranscribeError::QuotaExceeded { .. }
+            | TranscribeError::AlreadyTranscribing
+            | TranscribeError::Cancelled
+            | TranscribeError::Backend(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for TranscribeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_builder() {
+            TranscribeError::Backend(format!("Invalid request: {}", e))
+        } else if e.is_decode() {
+            TranscribeError::InvalidResponse(e.to_string())
+        } else {
+            TranscribeError::NetworkDown(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for TranscribeError {
+    fn from(e: serde_json::Error) -> Self {
+        TranscribeError::InvalidResponse(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_status_codes() {
+        let from = |status: StatusCode| TranscribeError::from_status(status, None, " details \n");
+        assert!(matches!(
+            from(StatusCode::UNAUTHORIZED),
+            TranscribeError::InvalidApiKey
+        ));
+        assert!(matches!(
+            from(StatusCode::FORBIDDEN),
+            TranscribeError::InvalidApiKey
+        ));
+        assert!(matches!(
+            from(StatusCode::PAYLOAD_TOO_LARGE),
+            TranscribeError::PayloadTooLarge
+        ));
+        assert!(matches!(
+            from(StatusCode::BAD_GATEWAY),
+            TranscribeError::ProviderServerError { status: 502, ref message } if message == "details"
+        ));
+        assert!(matches!(
+            from(StatusCode::NOT_FOUND),
+            TranscribeError::ProviderClientError { status: 404, ref message } if message == "details"
+        ));
+    }
+
+    #[test]
+    fn rate_limit_keeps_the_wait() {
+        let wait = Some(Duration::from_secs(7));
+        let error = TranscribeError::from_status(StatusCode::TOO_MANY_REQUESTS, wait, "");
+        assert!(
+            matches!(error, TranscribeError::RateLimited { retry_after } if retry_after == wait)
+        );
+        assert!(error.user_message().contains("7 seconds"));
+    }
+
+    #[test]
+    fn recovery_actions() {
+        let action =
+            |status: StatusCode| TranscribeError::from_status(status, None, "").recovery_action();
+        assert_eq!(
+            action(StatusCode::UNAUTHORIZED),
+            Some(RecoveryAction::EnterApiKey)
+        );
+        assert_eq!(
+            action(StatusCode::SERVICE_UNAVAILABLE),
+            Some(RecoveryAction::Retry)
+        );
+        assert_eq!(action(StatusCode::BAD_REQUEST), None);
+        assert!(TranscribeError::NetworkDown(String::new()).is_retryable());
+        assert!(!TranscribeError::PayloadTooLarge.is_retryable());
+    }
+}
@@ -0,0 +1,330 @@
+use super::live::VAD_WINDOW_SECS;
+use super::transcript::words_of;
+use super::{AudioInput, Segment, Transcript};
+use crate::config::Config;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+/// Text Whisper is known to invent on silence, learned from video subtitles.
+const PHANTOM_PHRASES: &[&str] = &[
+    "thank you for watching",
+    "thanks for watching",
+    "thank you so much for watching",
+    "please subscribe",
+    "like and subscribe",
+    "subtitles by the amara.org community",
+    "transcription by castingwords",
+    "you",
+    "thank you",
+    "bye",
+    "untertitel der amara.org-community",
+    "untertitelung des zdf",
+    "sous-titres réalisés par la communauté d'amara.org",
+    "продолжение следует",
+];
+/// A phrase repeated this often in a row within one segment is a decoding loop.
+const MAX_PHRASE_REPEATS: usize = 3;
+/// Share of the recording's quietest windows that is taken as background noise.
+const NOISE_FLOOR_QUANTILE: f64 = 0.1;
+/// A segment whose loudest window stays below this multiple of the noise
+/// floor (about 6 dB above it) holds no speech.
+const SILENCE_NOISE_FACTOR: f32 = 2.0;
+/// Below this RMS level (-80 dBFS) audio is silent whatever the noise floor.
+const DIGITAL_SILENCE_RMS: f32 = 1e-4;
+/// Phantom phrases are only suspicious when the audio stays below this multiple
+/// of the silence level.
+const PHANTOM_LEVEL_FACTOR: f32 = 3.0;
+/// Phantom phrases are also suspicious above this no-speech probability.
+const PHANTOM_NO_SPEECH_PROB: f64 = 0.3;
+
+/// What happens to segments that look made up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HallucinationAction {
+    /// Remove them from the transcript
+    #[default]
+    Drop,
+    /// Keep them, highlighted like low-confidence passages
+    Flag,
+    /// Leave the transcript as the provider returned it
+    Keep,
+}
+
+/// Why a segment was taken for a hallucination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HallucinationReason {
+    /// There is text although the audio never rises above the background noise
+    SilentAudio,
+    /// The model itself rated the segment as most likely not speech
+    NoSpeech,
+    /// The segment repeats the previous one or loops over a phrase
+    Repetition,
+    /// A phrase Whisper invents on silence, over quiet audio
+    PhantomPhrase,
+}
+
+/// Checks transcript segments against the audio they came from.
+#[derive(Debug, Clone)]
+pub struct HallucinationDetector {
+    action: HallucinationAction,
+    phrases: Vec<Vec<String>>,
+    min_avg_logprob: f64,
+    max_no_speech_prob: f64,
+}
+
+impl HallucinationDetector {
+    pub fn from_config(config: &Config) -> Self {
+        let phrases = PHANTOM_PHRASES
+            .iter()
+            .copied()
+            .chain(config.hallucination_phrases.iter().map(String::as_str))
+            .map(words_of)
+            .filter(|words| !words.is_empty())
+            .collect();
+        Self {
+            action: config.hallucination_action,
+            phrases,
+            min_avg_logprob: config.low_confidence_logprob,
+            max_no_speech_prob: config.low_confidence_no_speech_prob,
+        }
+    }
+
+    /// Drops or flags suspicious segments of `transcript`, whose timestamps
+    /// refer to `audio`. Without segments the whole text is checked.
+    pub fn apply(&self, transcript: &mut Transcript, audio: &AudioInput) {
+        if self.action == HallucinationAction::Keep || transcript.text.trim().is_empty() {
+            return;
+        }
+        if transcript.segments.is_empty() {
+            transcript.segments.push(Segment {
+                start: 0.0,
+                end: audio.duration_secs(),
+                text: transcript.text.clone(),
+                ..Segment::default()
+            });
+        }
+
+        // Relative to the recording's own noise, so quiet microphones keep their speech
+        let silence_level = (noise_floor(audio) * SILENCE_NOISE_FACTOR).max(DIGITAL_SILENCE_RMS);
+        debug!(
+            "Silence level for hallucination checks: {:.5}",
+            silence_level
+        );
+        let mut previous: Option<Vec<String>> = None;
+        let mut suspicious = 0;
+        for segment in &mut transcript.segments {
+            let words = words_of(&segment.text);
+            let reason = self.detect(segment, &words, previous.as_deref(), audio, silence_level);
+            previous = Some(words);
+            if let Some(reason) = reason {
+                info!(
+                    "Suspected hallucination ({:?}) at {:.1}s: {:?}",
+                    reason,
+                    segment.start,
+                    segment.text.trim()
+                );
+                segment.hallucination = Some(reason);
+                suspicious += 1;
+            }
+        }
+        if suspicious == 0 || self.action == HallucinationAction::Flag {
+            return;
+        }
+
+        transcript
+            .segments
+            .retain(|segment| segment.hallucination.is_none());
+        transcript.text = transcript
+            .segments
+            .iter()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        debug!("Dropped {} suspected hallucination(s)", suspicious);
+    }
+
+    fn detect(
+        &self,
+        segment: &Segment,
+        words: &[String],
+        previous: Option<&[String]>,
+        audio: &AudioInput,
+        silence_level: f32,
+    ) -> Option<HallucinationReason> {
+        if words.is_empty() {
+            return None;
+        }
+        let level = peak_level(audio, segment.start, segment.end);
+        if level.is_some_and(|level| level < silence_level) {
+            return Some(HallucinationReason::SilentAudio);
+        }
+        // Whisper's own silence rule: likely no speech and unsure about the words
+        if segment
+            .no_speech_prob
+            .is_some_and(|probability| probability > self.max_no_speech_prob)
+            && segment
+                .avg_logprob
+                .is_some_and(|logprob| logprob < self.min_avg_logprob)
+        {
+            return Some(HallucinationReason::NoSpeech);
+        }
+        // Single words are repeated in real speech ("no, no"), sentences rarely
+        if (words.len() > 1 && previous == Some(words)) || loops(words) {
+            return Some(HallucinationReason::Repetition);
+        }
+        let quiet = level.is_some_and(|level| level < silence_level * PHANTOM_LEVEL_FACTOR)
+            || segment
+                .no_speech_prob
+                .is_some_and(|probability| probability > PHANTOM_NO_SPEECH_PROB);
+        if quiet && self.phrases.iter().any(|phrase| phrase == words) {
+            return Some(HallucinationReason::PhantomPhrase);
+        }
+        None
+    }
+}
+
+/// Whether some phrase repeats back to back more than `MAX_PHRASE_REPEATS` times.
+fn loops(words: &[String]) -> bool {
+    let limit = MAX_PHRASE_REPEATS + 1;
+    (1..=words.len() / limit).any(|length| {
+        (0..=words.len() - length * limit).any(|start| {
+            let phrase = &words[start..start + length];
+            (1..limit).all(|repeat| {
+                let offset = start + repeat * length;
+                &words[offset..offset + length] == phrase
+            })
+        })
+    })
+}
+
+/// Loudest short-window RMS level between `start` and `end` seconds, or
+/// `None` when the range lies outside the audio.
+fn peak_level(audio: &AudioInput, start: f64, end: f64) -> Option<f32> {
+    let channels = audio.channels.max(1) as usize;
+    let frames = audio.samples.len() / channels;
+    let frame_at = |secs: f64| ((secs.max(0.0) * audio.sample_rate as f64) as usize).min(frames);
+    let (first, last) = (frame_at(start), frame_at(end));
+    if first >= last {
+        return None;
+    }
+    window_levels(audio, &audio.samples[first * channels..last * channels]).reduce(f32::max)
+}
+
+/// Level of the background noise: the RMS that `NOISE_FLOOR_QUANTILE` of the
+/// recording's windows stay below.
+fn noise_floor(audio: &AudioInput) -> f32 {
+    let mut levels: Vec<f32> = window_levels(audio, &audio.samples).collect();
+    if levels.is_empty() {
+        return 0.0;
+    }
+    levels.sort_by(f32::total_cmp);
+    levels[((levels.len() - 1) as f64 * NOISE_FLOOR_QUANTILE) as usize]
+}
+
+/// RMS level of every `VAD_WINDOW_SECS` window of `samples`, a slice of `audio`.
+fn window_levels<'a>(audio: &AudioInput, samples: &'a [f32]) -> impl Iterator<Item = f32> + 'a {
+    let channels = audio.channels.max(1) as usize;
+    let window = ((VAD_WINDOW_SECS * audio.sample_rate as f64) as usize).max(1) * channels;
+    samples
+        .chunks(window)
+        .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::test_util::{recording, segment};
+
+    fn detector(action: HallucinationAction) -> HallucinationDetector {
+        HallucinationDetector::from_config(&Config {
+            hallucination_action: action,
+            ..Config::default()
+        })
+    }
+
+    fn transcript(segments: Vec<Segment>) -> Transcript {
+        Transcript {
+            text: segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            segments,
+            ..Transcript::default()
+        }
+    }
+
+    #[test]
+    fn drops_text_over_background_noise() {
+        let mut transcript = transcript(vec![
+            segment(0.0, 1.0, "Invented words."),
+            segment(1.0, 2.0, "Real speech."),
+        ]);
+        detector(HallucinationAction::Drop)
+            .apply(&mut transcript, &recording(&[(0.002, 1.0), (0.05, 1.0)]));
+        assert_eq!(transcript.text, "Real speech.");
+        assert_eq!(transcript.segments.len(), 1);
+    }
+
+    #[test]
+    fn keeps_quiet_speech_in_a_quiet_recording() {
+        // Speech around -50 dBFS, well below a fixed speech threshold
+        let mut transcript = transcript(vec![segment(1.0, 2.0, "Whispered speech.")]);
+        detector(HallucinationAction::Drop)
+            .apply(&mut transcript, &recording(&[(0.0002, 1.0), (0.003, 1.0)]));
+        assert_eq!(transcript.text, "Whispered speech.");
+    }
+
+    #[test]
+    fn flags_phantom_phrases_over_quiet_audio() {
+        let mut transcript = transcript(vec![
+            segment(2.0, 3.0, "See you tomorrow."),
+            segment(1.0, 2.0, "Thanks for watching!"),
+        ]);
+        // A rustle above the noise floor, but far from speech level
+        let audio = recording(&[(0.001, 1.0), (0.003, 1.0), (0.05, 1.0)]);
+        detector(HallucinationAction::Flag).apply(&mut transcript, &audio);
+        assert_eq!(transcript.text, "See you tomorrow. Thanks for watching!");
+        assert_eq!(transcript.segments[0].hallucination, None);
+        assert_eq!(
+            transcript.segments[1].hallucination,
+            Some(HallucinationReason::PhantomPhrase)
+        );
+    }
+
+    #[test]
+    fn flags_repeated_segments() {
+        let mut transcript = transcript(vec![
+            segment(1.0, 1.5, "We should go."),
+            segment(1.5, 2.0, "We should go."),
+        ]);
+        detector(HallucinationAction::Flag)
+            .apply(&mut transcript, &recording(&[(0.002, 1.0), (0.05, 1.0)]));
+        assert_eq!(
+            transcript.segments[1].hallucination,
+            Some(HallucinationReason::Repetition)
+        );
+    }
+
+    #[test]
+    fn keep_leaves_the_transcript_alone() {
+        let mut transcript = transcript(vec![segment(0.0, 1.0, "Invented words.")]);
+        let original = transcript.clone();
+        detector(HallucinationAction::Keep)
+            .apply(&mut transcript, &recording(&[(0.002, 1.0), (0.05, 1.0)]));
+        assert_eq!(transcript, original);
+    }
+
+    #[test]
+    fn detects_phrase_loops() {
+        assert!(loops(&words_of("go go go go")));
+        assert!(loops(&words_of(
+            "and then I and then I and then I and then I"
+        )));
+        assert!(!loops(&words_of("go go go")));
+        assert!(!loops(&words_of("no, no, I said no")));
+        assert!(!loops(&[]));
+    }
+}
@@ -0,0 +1,321 @@
+use super::chunking::{stitch, AudioChunk};
+use super::{AudioInput, TranscribeError, Transcript};
+use log::debug;
+use std::time::Duration;
+
+/// How often the recording is checked for finished utterances.
+pub const LIVE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Voice activity is decided per window of this length.
+pub(super) const VAD_WINDOW_SECS: f64 = 0.03;
+/// RMS level above which a window counts as speech, about -40 dBFS.
+const SPEECH_RMS_THRESHOLD: f32 = 0.01;
+/// Utterances with less speech than this are clicks or breaths and are dropped.
+const MIN_SPEECH_SECS: f64 = 0.2;
+/// Silence kept in front of an utterance so its first word is not clipped.
+const LEADING_SILENCE_SECS: f64 = 0.3;
+/// Speech without a pause is cut after this long, so text keeps appearing.
+const MAX_UTTERANCE_SECS: f64 = 30.0;
+
+/// What the voice activity detector found at the start of the pending audio.
+#[derive(Debug, PartialEq)]
+enum Scan {
+    /// Speech between these frames, followed by a pause or the end of the recording
+    Utterance { start: usize, end: usize },
+    /// Nothing worth transcribing before this frame
+    Silence { end: usize },
+    /// Too little speech to transcribe, e.g. a click or a breath, before this
+    /// frame; more speech may follow
+    Noise { end: usize },
+    /// Someone is still speaking; wait for more audio
+    Incomplete,
+}
+
+/// Cuts a growing recording into utterances at pauses in the speech.
+#[derive(Debug)]
+pub struct UtteranceSplitter {
+    sample_rate: u32,
+    channels: u16,
+    min_silence_secs: f64,
+    /// Frames of the recording already handed out or dropped as silence
+    consumed: usize,
+}
+
+impl UtteranceSplitter {
+    pub fn new(sample_rate: u32, channels: u16, min_silence: Duration) -> Self {
+        Self {
+            sample_rate,
+            channels: channels.max(1),
+            min_silence_secs: min_silence.as_secs_f64(),
+            consumed: 0,
+        }
+    }
+
+    /// Index of the first recorded sample that has not been split off yet.
+    pub fn consumed_samples(&self) -> usize {
+        self.consumed * self.channels as usize
+    }
+
+    /// Splits finished utterances off `pending`, the samples recorded since
+    /// `consumed_samples`. Speech still in progress is left for the next call.
+    pub fn split(&mut self, pending: &[f32]) -> Vec<AudioChunk> {
+        self.split_pending(pending, false)
+    }
+
+    /// Splits off everything that is left once the recording has stopped.
+    pub fn finish(&mut self, pending: &[f32]) -> Vec<AudioChunk> {
+        self.split_pending(pending, true)
+    }
+
+    fn split_pending(&mut self, mut pending: &[f32], finished: bool) -> Vec<AudioChunk> {
+        let channels = self.channels as usize;
+        let mut utterances = Vec::new();
+        loop {
+            match self.scan(pending, finished) {
+                Scan::Utterance { start, end } => {
+                    utterances.push(AudioChunk {
+                        offset: (self.consumed + start) as f64 / self.sample_rate as f64,
+                        keep_from: 0.0,
+                        audio: AudioInput {
+                            samples: pending[start * channels..end * channels].to_vec(),
+                            sample_rate: self.sample_rate,
+                            channels: self.channels,
+                        },
+                    });
+                    self.consumed += end;
+                    pending = &pending[end * channels..];
+                }
+                Scan::Silence { end } => {
+                    self.consumed += end;
+                    break;
+                }
+                Scan::Noise { end } => {
+                    self.consumed += end;
+                    pending = &pending[end * channels..];
+                }
+                Scan::Incomplete => break,
+            }
+        }
+        if !utterances.is_empty() {
+            debug!(
+                "Split off {} utterance(s), {:.1}s of the recording consumed",
+                utterances.len(),
+                self.consumed as f64 / self.sample_rate as f64
+            );
+        }
+        utterances
+    }
+
+    fn scan(&self, samples: &[f32], finished: bool) -> Scan {
+        let channels = self.channels as usize;
+        let frames = samples.len() / channels;
+        let window = ((VAD_WINDOW_SECS * self.sample_rate as f64) as usize).max(1);
+        let windows_for = |secs: f64| (secs / VAD_WINDOW_SECS).ceil() as usize;
+
+        // A trailing partial window only counts once no more audio will arrive
+        let levels: Vec<f32> = samples
+            .chunks(window * channels)
+            .filter(|chunk| finished || chunk.len() == window * channels)
+            .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+            .collect();
+        let is_speech = |level: f32| level >= SPEECH_RMS_THRESHOLD;
+        let lead = windows_for(LEADING_SILENCE_SECS);
+
+        let Some(first_speech) = levels.iter().position(|&level| is_speech(level)) else {
+            return Scan::Silence {
+                end: if finished {
+                    frames
+                } else {
+                    levels.len().saturating_sub(lead) * window
+                },
+            };
+        };
+        let start = first_speech.saturating_sub(lead);
+        let min_silence = windows_for(self.min_silence_secs).max(1);
+        let max_windows = windows_for(MAX_UTTERANCE_SECS);
+
+        let mut end = None;
+        let mut silent_run = 0;
+        for (i, &level) in levels.iter().enumerate().skip(first_speech) {
+            silent_run = if is_speech(level) { 0 } else { silent_run + 1 };
+            if silent_run >= min_silence {
+                // Cut in the middle of the pause
+                end = Some((i + 1 - silent_run / 2) * window);
+                break;
+            }
+            if i + 1 - start >= max_windows {
+                // No pause for too long: cut at the quietest window of the last quarter
+                let quietest = (i + 1 - max_windows / 4..=i)
+                    .min_by(|&a, &b| levels[a].total_cmp(&levels[b]))
+                    .unwrap_or(i);
+                end = Some(quietest * window + window / 2);
+                break;
+            }
+        }
+        let end = match end {
+            Some(end) => end,
+            None if finished => frames,
+            None => return Scan::Incomplete,
+        };
+
+        let speech_windows = levels[first_speech..end.div_ceil(window).min(levels.len())]
+            .iter()
+            .filter(|&&level| is_speech(level))
+            .count();
+        if speech_windows < windows_for(MIN_SPEECH_SECS) {
+            return Scan::Noise { end };
+        }
+        Scan::Utterance {
+            start: start * window,
+            end,
+        }
+    }
+}
+
+/// Utterance transcripts of a live recording, kept in recording order while
+/// they complete in any order.
+#[derive(Debug, Default)]
+pub struct LiveTranscript {
+    parts: Vec<(AudioChunk, Option<Transcript>)>,
+    failures: Vec<TranscribeError>,
+}
+
+impl LiveTranscript {
+    /// Registers an utterance that is being transcribed and returns its index.
+    pub fn push(&mut self, utterance: AudioChunk) -> usize {
+        self.parts.push((utterance, None));
+        self.parts.len() - 1
+    }
+
+    pub fn complete(&mut self, index: usize, transcript: Transcript) {
+        if let Some(part) = self.parts.get_mut(index) {
+            part.1 = Some(transcript);
+        }
+    }
+
+    /// Records an utterance that could not be transcribed; it is left out.
+    pub fn fail(&mut self, error: TranscribeError) {
+        self.failures.push(error);
+    }
+
+    /// The first failure, if utterances failed and none was transcribed.
+    pub fn total_failure(&self) -> Option<TranscribeError> {
+        if self
+            .parts
+            .iter()
+            .any(|(_, transcript)| transcript.is_some())
+        {
+            return None;
+        }
+        self.failures.first().cloned()
+    }
+
+    /// Everything transcribed so far, on the recording's timeline.
+    pub fn transcript(&self) -> Transcript {
+        let parts = self
+            .parts
+            .iter()
+            .filter_map(|(chunk, transcript)| Some((chunk, transcript.clone()?)))
+            .collect::<Vec<_>>();
+        let duration = self
+            .parts
+            .last()
+            .map(|(chunk, _)| chunk.offset + chunk.audio.duration_secs())
+            .unwrap_or_default();
+        stitch(&parts, duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::test_util::{recording, samples, SAMPLE_RATE};
+
+    fn splitter() -> UtteranceSplitter {
+        UtteranceSplitter::new(SAMPLE_RATE, 1, Duration::from_millis(500))
+    }
+
+    fn split(splitter: &mut UtteranceSplitter, recording: &[f32]) -> Vec<AudioChunk> {
+        splitter.split(&recording[splitter.consumed_samples()..])
+    }
+
+    #[test]
+    fn silence_is_consumed_but_keeps_the_lead_in() {
+        let mut splitter = splitter();
+        let recording = samples(&[(0.0, 2.0)]);
+        assert!(split(&mut splitter, &recording).is_empty());
+        let consumed = splitter.consumed_samples() as f64 / SAMPLE_RATE as f64;
+        assert!(
+            (consumed - (2.0 - LEADING_SILENCE_SECS)).abs() < 0.05,
+            "{}",
+            consumed
+        );
+    }
+
+    #[test]
+    fn waits_for_a_pause_before_splitting() {
+        let mut splitter = splitter();
+        let mut audio = samples(&[(0.0, 1.0), (0.1, 1.0)]);
+        assert!(split(&mut splitter, &audio).is_empty());
+
+        audio.extend(samples(&[(0.0, 1.0)]));
+        let utterances = split(&mut splitter, &audio);
+        assert_eq!(utterances.len(), 1);
+        let utterance = &utterances[0];
+        assert!((utterance.offset - (1.0 - LEADING_SILENCE_SECS)).abs() < 0.05);
+        let duration = utterance.audio.duration_secs();
+        assert!(duration > 1.3 && duration < 1.8, "{}", duration);
+    }
+
+    #[test]
+    fn speech_after_a_click_is_kept() {
+        let mut splitter = splitter();
+        let audio = samples(&[(0.1, 0.05), (0.0, 1.0), (0.1, 1.0), (0.0, 0.2)]);
+        let utterances = splitter.finish(&audio);
+        assert_eq!(utterances.len(), 1);
+        assert!((utterances[0].offset - 0.75).abs() < 0.05);
+    }
+
+    #[test]
+    fn finish_flushes_speech_in_progress() {
+        let mut splitter = splitter();
+        let audio = samples(&[(0.1, 1.0)]);
+        assert!(split(&mut splitter, &audio).is_empty());
+        assert_eq!(splitter.finish(&audio).len(), 1);
+        assert_eq!(splitter.consumed_samples(), audio.len());
+    }
+
+    #[test]
+    fn long_speech_is_cut() {
+        let mut splitter = splitter();
+        let audio = samples(&[(0.1, MAX_UTTERANCE_SECS + 5.0)]);
+        let utterances = split(&mut splitter, &audio);
+        assert_eq!(utterances.len(), 1);
+        assert!(utterances[0].audio.duration_secs() <= MAX_UTTERANCE_SECS);
+    }
+
+    #[test]
+    fn live_transcript_keeps_recording_order() {
+        let utterance = |offset: f64| AudioChunk {
+            offset,
+            keep_from: 0.0,
+            audio: recording(&[(0.0, 1.0)]),
+        };
+        let text = |text: &str| Transcript {
+            text: text.to_string(),
+            ..Transcript::default()
+        };
+        let mut live = LiveTranscript::default();
+        let first = live.push(utterance(0.0));
+        // The middle utterance fails and is left out
+        live.push(utterance(2.0));
+        let third = live.push(utterance(4.0));
+        live.fail(TranscribeError::Backend("offline".to_string()));
+        assert!(live.total_failure().is_some());
+
+        live.complete(third, text("three"));
+        live.complete(first, text("one"));
+        assert_eq!(live.transcript().text, "one three");
+        assert_eq!(live.transcript().duration, Some(5.0));
+        assert!(live.total_failure().is_none());
+    }
+}
@@ -0,0 +1,166 @@
+use super::{
+    AudioInput, Segment, TranscribeError, Transcript, TranscriptionBackend, TranscriptionMode,
+    TranscriptionOptions, AUTO_LANGUAGE, WHISPER_SAMPLE_RATE,
+};
+use crate::audio::{downmix_to_mono, resample};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{debug, info};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+// Loading a GGML model takes seconds, so the last one used is kept around.
+static CONTEXT_CACHE: Mutex<Option<(PathBuf, Arc<WhisperContext>)>> = Mutex::new(None);
+
+/// Runs a whisper.cpp GGML model on the CPU, without any network access.
+pub struct LocalWhisperBackend {
+    model_path: PathBuf,
+    threads: i32,
+}
+
+impl LocalWhisperBackend {
+    pub fn new(model_path: PathBuf) -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get() as i32)
+            .unwrap_or(4);
+        Self {
+            model_path,
+            threads,
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for LocalWhisperBackend {
+    fn name(&self) -> &str {
+        "Local Whisper"
+    }
+
+    fn supports_long_audio(&self) -> bool {
+        true
+    }
+
+    async fn transcribe(
+        &self,
+        audio: &AudioInput,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript, TranscribeError> {
+        let mono = downmix_to_mono(&audio.samples, audio.channels);
+        let samples = resample(&mono, audio.sample_rate, WHISPER_SAMPLE_RATE);
+        let model_path = self.model_path.clone();
+        let threads = self.threads;
+        let options = options.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let context = load_context(&model_path)?;
+            run_whisper(&context, &samples, threads, &options)
+        })
+        .await
+        .context("Local whisper task panicked")
+        .and_then(|result| result)
+        .map_err(|e| TranscribeError::Backend(format!("{:#}", e)))
+    }
+}
+
+fn load_context(model_path: &Path) -> Result<Arc<WhisperContext>> {
+    let mut cache = CONTEXT_CACHE.lock().unwrap();
+    if let Some((path, context)) = cache.as_ref() {
+        if path == model_path {
+            debug!("Reusing cached whisper model {:?}", model_path);
+            return Ok(Arc::clone(context));
+        }
+    }
+
+    info!("Loading whisper model from {:?}", model_path);
+    let path = model_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Model path {:?} is not valid UTF-8", model_path))?;
+    let context = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+        .with_context(|| format!("Failed to load whisper model {:?}", model_path))?;
+    let context = Arc::new(context);
+    *cache = Some((model_path.to_path_buf(), Arc::clone(&context)));
+    Ok(context)
+}
+
+fn run_whisper(
+    context: &WhisperContext,
+    samples: &[f32],
+    threads: i32,
+    options: &TranscriptionOptions,
+) -> Result<Transcript> {
+    debug!(
+        "Running local whisper on {} samples with {} threads",
+        samples.len(),
+        threads
+    );
+    let mut state = context
+        .create_state()
+        .context("Failed to create whisper state")?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(threads);
+    params.set_language(Some(options.language.as_deref().unwrap_or(AUTO_LANGUAGE)));
+    params.set_translate(options.mode == TranscriptionMode::Translate);
+    if let Some(prompt) = &options.prompt {
+        params.set_initial_prompt(prompt);
+    }
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+
+    state
+        .full(params, samples)
+        .context("Local whisper transcription failed")?;
+
+    let segment_count = state.full_n_segments()?;
+    let eot = context.token_eot();
+    let mut segments = Vec::with_capacity(segment_count as usize);
+    for segment in 0..segment_count {
+        // Timestamps are reported in centiseconds
+        let start = state.full_get_segment_t0(segment)? as f64 / 100.0;
+        let end = state.full_get_segment_t1(segment)? as f64 / 100.0;
+        let text = state.full_get_segment_text(segment)?;
+
+        // Special tokens (timestamps, end of text) would skew the confidence
+        let mut logprob_sum = 0.0;
+        let mut token_count = 0;
+        for token in 0..state.full_n_tokens(segment)? {
+            let data = state.full_get_token_data(segment, token)?;
+            if data.id < eot {
+                logprob_sum += data.plog as f64;
+                token_count += 1;
+            }
+        }
+        let avg_logprob = (token_count > 0).then(|| logprob_sum / token_count as f64);
+
+        segments.push(Segment {
+            start,
+            end,
+            text: text.trim().to_string(),
+            avg_logprob,
+            no_speech_prob: None,
+            hallucination: None,
+        });
+    }
+    let text = segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let language = state
+        .full_lang_id_from_state()
+        .ok()
+        .and_then(whisper_rs::get_lang_str)
+        .map(str::to_string);
+
+    Ok(Transcript {
+        text,
+        language,
+        duration: Some(samples.len() as f64 / WHISPER_SAMPLE_RATE as f64),
+        segments,
+        provider: None,
+    })
+}
@@ -0,0 +1,273 @@
+use crate::audio::{downmix_to_mono, resample};
+use crate::config::Config;
+use crate::usage::UsageLog;
+use async_trait::async_trait;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+mod chunking;
+mod error;
+mod hallucination;
+mod http;
+mod live;
+#[cfg(feature = "local-whisper")]
+mod local;
+mod models;
+mod openai;
+mod prompt;
+mod retry;
+#[cfg(test)]
+mod test_util;
+mod transcript;
+
+pub use chunking::transcribe_chunked;
+pub use error::{RecoveryAction, TranscribeError};
+pub use hallucination::{HallucinationAction, HallucinationDetector};
+pub use http::build_http_client;
+pub use live::{LiveTranscript, UtteranceSplitter, LIVE_POLL_INTERVAL};
+#[cfg(feature = "local-whisper")]
+pub use local::LocalWhisperBackend;
+pub use models::{load_cached_models, store_cached_models};
+pub use openai::{OpenAiCompatibleBackend, DEFAULT_MODEL, GROQ_API_ENDPOINT};
+pub use retry::RetryPolicy;
+pub use transcript::{Segment, Transcript};
+
+/// Raw audio handed to a backend, exactly as captured by the recorder.
+#[derive(Debug, Clone)]
+pub struct AudioInput {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Whisper-family models work on 16 kHz mono audio; anything more is wasted upload.
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+impl AudioInput {
+    pub fn duration_secs(&self) -> f64 {
+        self.samples.len() as f64 / (self.sample_rate as f64 * self.channels as f64)
+    }
+
+    /// Downmixes to mono and resamples to `WHISPER_SAMPLE_RATE`.
+    pub fn to_whisper_format(&self) -> AudioInput {
+        let mono = downmix_to_mono(&self.samples, self.channels);
+        AudioInput {
+            samples: resample(&mono, self.sample_rate, WHISPER_SAMPLE_RATE),
+            sample_rate: WHISPER_SAMPLE_RATE,
+            channels: 1,
+        }
+    }
+}
+
+/// Progress reported while a request is held up, for display in the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptionStatus {
+    /// The provider returned HTTP 429 and asked us to wait
+    RateLimited { wait: Duration },
+    /// A connection error or 5xx response; the request will be sent again
+    Retrying {
+        attempt: u32,
+        max_retries: u32,
+        delay: Duration,
+    },
+    /// A provider failed and the next one in the fallback chain is tried
+    FallingBack { failed: String, next: String },
+    /// This month's audio passed `Config::soft_quota_minutes`
+    QuotaWarning {
+        used_minutes: f64,
+        quota_minutes: f64,
+    },
+    /// One live utterance could not be transcribed; the session goes on
+    UtteranceFailed { message: String },
+}
+
+pub type StatusSender = broadcast::Sender<TranscriptionStatus>;
+
+/// Language code meaning "let the model detect the spoken language".
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Whether speech is written down as spoken or translated into English.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionMode {
+    #[default]
+    Transcribe,
+    /// Translate speech in any supported language into English text
+    Translate,
+}
+
+/// Per-request settings shared by every backend.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscriptionOptions {
+    pub mode: TranscriptionMode,
+    /// ISO-639-1 code of the spoken language, or `None` to auto-detect it
+    pub language: Option<String>,
+    /// Glossary and style hints, already truncated to the provider's limit
+    pub prompt: Option<String>,
+}
+
+impl TranscriptionOptions {
+    pub fn from_config(config: &Config) -> Self {
+        let language = config.language.trim();
+        Self {
+            mode: config.mode,
+            language: if language.is_empty() || language == AUTO_LANGUAGE {
+                None
+            } else {
+                Some(language.to_string())
+            },
+            prompt: prompt::build_prompt(&config.glossary, &config.prompt),
+        }
+    }
+}
+
+/// Which transcription backend the application should use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    #[default]
+    Groq,
+    /// Any self-hosted or third-party server implementing the OpenAI audio API
+    OpenAiCompatible,
+    Local,
+}
+
+impl BackendKind {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            BackendKind::Groq => "Groq",
+            BackendKind::OpenAiCompatible => "OpenAI-compatible",
+            BackendKind::Local => "Local Whisper",
+        }
+    }
+}
+
+/// One provider in the fallback chain: a backend plus where and what to ask it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderConfig {
+    /// Label shown in the UI; defaults to the backend's display name
+    pub name: Option<String>,
+    pub backend: BackendKind,
+    /// Base URL of the OpenAI-compatible API; unused by the local backend
+    pub api_endpoint: String,
+    pub model: String,
+    /// Environment variable holding this provider's API key. Without it, Groq
+    /// providers use the key saved in the keyring and others send none.
+    pub api_key_env: Option<String>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            name: None,
+            backend: BackendKind::Groq,
+            api_endpoint: GROQ_API_ENDPOINT.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            api_key_env: None,
+        }
+    }
+}
+
+impl ProviderConfig {
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .unwrap_or_else(|| self.backend.display_name())
+    }
+
+    /// The API key for a fallback provider; see `api_key_env`.
+    pub fn fallback_api_key(&self, saved_key: Option<&str>) -> Option<String> {
+        match &self.api_key_env {
+            Some(variable) => std::env::var(variable).ok(),
+            None if self.backend == BackendKind::Groq => saved_key.map(str::to_string),
+            None => None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait TranscriptionBackend: Send + Sync {
+    /// Human readable provider name, used for logging and the UI.
+    fn name(&self) -> &str;
+
+    /// Whether the backend accepts recordings of any length. Backends that
+    /// upload audio are limited by `Config::max_file_size_bytes` and get the
+    /// recording in chunks instead.
+    fn supports_long_audio(&self) -> bool {
+        false
+    }
+
+    async fn transcribe(
+        &self,
+        audio: &AudioInput,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript, TranscribeError>;
+}
+
+/// Builds the backend for `provider`, with the shared settings from `config`.
+pub fn create_backend(
+    config: &Config,
+    provider: &ProviderConfig,
+    api_key: Option<String>,
+    client: &reqwest::Client,
+    status: &StatusSender,
+) -> Result<Arc<dyn TranscriptionBackend>, TranscribeError> {
+    debug!("Creating transcription backend: {:?}", provider.backend);
+    match provider.backend {
+        BackendKind::Groq => {
+            let api_key = api_key.ok_or(TranscribeError::NoApiKey)?;
+            Ok(Arc::new(
+                OpenAiCompatibleBackend::new(
+                    client.clone(),
+                    provider.display_name(),
+                    &provider.api_endpoint,
+                    Some(api_key),
+                )
+                .with_model(&provider.model)
+                .with_retry_policy(RetryPolicy::from_config(config))
+                .with_upload_codec(config.upload_codec)
+                .with_usage_log(UsageLog::default())
+                .with_status_sender(status.clone()),
+            ))
+        }
+        // Self-hosted servers frequently run without authentication
+        BackendKind::OpenAiCompatible => Ok(Arc::new(
+            OpenAiCompatibleBackend::new(
+                client.clone(),
+                provider.display_name(),
+                &provider.api_endpoint,
+                api_key,
+            )
+            .with_model(&provider.model)
+            .with_retry_policy(RetryPolicy::from_config(config))
+            .with_upload_codec(config.upload_codec)
+            .with_usage_log(UsageLog::default())
+            .with_status_sender(status.clone()),
+        )),
+        BackendKind::Local => create_local_backend(config),
+    }
+}
+
+#[cfg(feature = "local-whisper")]
+fn create_local_backend(config: &Config) -> Result<Arc<dyn TranscriptionBackend>, TranscribeError> {
+    let model_path = config.local_model_path.clone().ok_or_else(|| {
+        TranscribeError::Backend(
+            "No local whisper model is configured. Set local_model_path in the configuration."
+                .to_string(),
+        )
+    })?;
+    Ok(Arc::new(LocalWhisperBackend::new(model_path)))
+}
+
+#[cfg(not(feature = "local-whisper"))]
+fn create_local_backend(
+    _config: &Config,
+) -> Result<Arc<dyn TranscriptionBackend>, TranscribeError> {
+    Err(TranscribeError::Backend(
+        "Local transcription is unavailable: rebuild with `--features local-whisper`".to_string(),
+    ))
+}
@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Speech models last reported by a provider, kept on disk so the model
+/// picker is populated at startup without waiting for the network.
+#[derive(Debug, Serialize, Deserialize)]
+struct ModelCache {
+    base_url: String,
+    models: Vec<String>,
+}
+
+fn cache_path() -> PathBuf {
+    glib::user_cache_dir()
+        .join("voice_transcriber")
+        .join("models.json")
+}
+
+/// Returns the cached models, if they were fetched from `base_url`.
+pub fn load_cached_models(base_url: &str) -> Vec<String> {
+    let path = cache_path();
+    let cache: ModelCache = match fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+    {
+        Some(cache) => cache,
+        None => return Vec::new(),
+    };
+    if cache.base_url != base_url {
+        debug!("Model cache belongs to {}, ignoring it", cache.base_url);
+        return Vec::new();
+    }
+    cache.models
+}
+
+pub fn store_cached_models(base_url: &str, models: &[String]) -> Result<()> {
+    let path = cache_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory {:?}", dir))?;
+    }
+    let cache = ModelCache {
+        base_url: base_url.to_string(),
+        models: models.to_vec(),
+    };
+    fs::write(&path, serde_json::to_string(&cache)?)
+        .with_context(|| format!("Failed to write model cache {:?}", path))?;
+    Ok(())
+}
+
+// The /models endpoint mixes chat, TTS and speech recognition models without a
+// type field, so speech models are recognised by name.
+pub fn is_speech_model(id: &str) -> bool {
+    let id = id.to_lowercase();
+    id.contains("whisper") || id.contains("transcribe")
+}
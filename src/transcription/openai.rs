@@ -0,0 +1,300 @@
+use super::models::is_speech_model;
+use super::retry::{retry_after, send_with_retry, RetryPolicy};
+use super::{
+    AudioInput, StatusSender, TranscribeError, Transcript, TranscriptionBackend, TranscriptionMode,
+    TranscriptionOptions,
+};
+use crate::audio::{encode_audio, UploadCodec};
+use crate::usage::{UsageLog, UsageRecord};
+use async_trait::async_trait;
+use log::{debug, error, warn};
+use std::time::Instant;
+
+pub const DEFAULT_MODEL: &str = "distil-whisper-large-v3-en";
+pub const GROQ_API_ENDPOINT: &str = "https://api.groq.com/openai/v1";
+
+/// Talks to any server implementing the OpenAI audio API: Groq, OpenAI itself,
+/// or self-hosted servers such as faster-whisper-server and LocalAI.
+pub struct OpenAiCompatibleBackend {
+    client: reqwest::Client,
+    name: String,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    retry_policy: RetryPolicy,
+    upload_codec: UploadCodec,
+    status: Option<StatusSender>,
+    usage_log: Option<UsageLog>,
+}
+
+impl OpenAiCompatibleBackend {
+    pub fn new(
+        client: reqwest::Client,
+        name: &str,
+        base_url: &str,
+        api_key: Option<String>,
+    ) -> Self {
+        Self {
+            client,
+            name: name.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model: DEFAULT_MODEL.to_string(),
+            retry_policy: RetryPolicy::default(),
+            upload_codec: UploadCodec::default(),
+            status: None,
+            usage_log: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_upload_codec(mut self, upload_codec: UploadCodec) -> Self {
+        self.upload_codec = upload_codec;
+        self
+    }
+
+    /// Reports retries and rate-limit waits to `status`.
+    pub fn with_status_sender(mut self, status: StatusSender) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Records every upload, successful or not, in `usage_log`.
+    pub fn with_usage_log(mut self, usage_log: UsageLog) -> Self {
+        self.usage_log = Some(usage_log);
+        self
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.header("Authorization", format!("Bearer {}", api_key)),
+            None => request,
+        }
+    }
+
+    /// Encodes the recording once per request; GStreamer blocks, so it runs off the runtime.
+    async fn encode(&self, audio: &AudioInput) -> Result<Vec<u8>, TranscribeError> {
+        let audio = audio.clone();
+        let codec = self.upload_codec;
+        tokio::task::spawn_blocking(move || {
+            encode_audio(&audio.samples, audio.sample_rate, audio.channels, codec)
+        })
+        .await
+        .map_err(|e| TranscribeError::AudioEncoding(e.to_string()))?
+        .map_err(|e| TranscribeError::AudioEncoding(format!("{:#}", e)))
+    }
+
+    /// Builds the multipart body shared by the transcription and translation endpoints.
+    fn build_form(
+        &self,
+        encoded: &[u8],
+        options: &TranscriptionOptions,
+    ) -> Result<reqwest::multipart::Form, TranscribeError> {
+        let file_part = reqwest::multipart::Part::bytes(encoded.to_vec())
+            .file_name(self.upload_codec.file_name())
+            .mime_str(self.upload_codec.mime_type())?;
+
+        // verbose_json is the only format that reports the language and segment timings
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", file_part)
+            .text("model", self.model.clone())
+            .text("temperature", "0")
+            .text("response_format", "verbose_json");
+        // The translations endpoint always produces English and rejects a language field
+        if let (TranscriptionMode::Transcribe, Some(language)) = (options.mode, &options.language) {
+            form = form.text("language", language.clone());
+        }
+        if let Some(prompt) = &options.prompt {
+            form = form.text("prompt", prompt.clone());
+        }
+        Ok(form)
+    }
+
+    async fn get_models(&self) -> Result<reqwest::Response, TranscribeError> {
+        let url = self.endpoint("models");
+        debug!("Requesting model list from {}", url);
+        let response = send_with_retry(&self.retry_policy, self.status.as_ref(), || {
+            Ok(self.authorize(self.client.get(&url)))
+        })
+        .await?;
+        debug!(
+            "Received response from {} with status: {}",
+            self.name,
+            response.status()
+        );
+        Ok(response)
+    }
+
+    /// Checks that the server accepts our credentials, by listing its models.
+    pub async fn validate_credentials(&self) -> Result<(), TranscribeError> {
+        let response = self.get_models().await?;
+        check_status(response).await.map(|_| ())
+    }
+
+    /// Lists the models the server offers for speech recognition, sorted by id.
+    pub async fn list_speech_models(&self) -> Result<Vec<String>, TranscribeError> {
+        let response = check_status(self.get_models().await?).await?;
+
+        let json: serde_json::Value = response.json().await?;
+        let mut models: Vec<String> = json["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model["id"].as_str())
+                    .filter(|id| is_speech_model(id))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        models.sort();
+        debug!("{} offers speech models: {:?}", self.name, models);
+        Ok(models)
+    }
+
+    /// Runs `text` through the chat completions endpoint with `instructions`
+    /// as the system prompt and returns the model's reply.
+    pub async fn complete_chat(
+        &self,
+        model: &str,
+        instructions: &str,
+        text: &str,
+    ) -> Result<String, TranscribeError> {
+        let url = self.endpoint("chat/completions");
+        let body = serde_json::json!({
+            "model": model,
+            "temperature": 0.2,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": text },
+            ],
+        });
+        debug!("Sending {} characters to {} ({})", text.len(), url, model);
+        let response = send_with_retry(&self.retry_policy, self.status.as_ref(), || {
+            Ok(self.authorize(self.client.post(&url)).json(&body))
+        })
+        .await?;
+        let response = check_status(response).await?;
+
+        let json: serde_json::Value = response.json().await?;
+        json["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|content| content.trim().to_string())
+            .ok_or_else(|| {
+                TranscribeError::InvalidResponse("Chat completion has no message".to_string())
+            })
+    }
+
+    /// Sends the encoded recording and parses the provider's answer.
+    async fn upload(
+        &self,
+        url: &str,
+        encoded: &[u8],
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript, TranscribeError> {
+        let response = send_with_retry(&self.retry_policy, self.status.as_ref(), || {
+            let form = self.build_form(encoded, options)?;
+            Ok(self.authorize(self.client.post(url)).multipart(form))
+        })
+        .await?;
+
+        debug!("Response status: {}", response.status());
+        let response = check_status(response).await?;
+
+        let response_text = response.text().await?;
+        debug!("Response body: {}", response_text);
+        // verbose_json deserializes straight into a Transcript; servers that
+        // ignore the format and answer with plain json just lack the segments
+        let mut transcript: Transcript = serde_json::from_str(&response_text)?;
+        if transcript.language.is_none() && options.mode == TranscriptionMode::Transcribe {
+            transcript.language = options.language.clone();
+        }
+        debug!(
+            "Parsed transcript with {} segments",
+            transcript.segments.len()
+        );
+        Ok(transcript)
+    }
+
+    fn log_usage(
+        &self,
+        audio: &AudioInput,
+        bytes: usize,
+        started: Instant,
+        result: &Result<Transcript, TranscribeError>,
+    ) {
+        let Some(usage_log) = &self.usage_log else {
+            return;
+        };
+        let record = UsageRecord {
+            timestamp: UsageRecord::now_timestamp(),
+            provider: self.name.clone(),
+            model: self.model.clone(),
+            audio_secs: audio.duration_secs(),
+            bytes,
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+        if let Err(e) = usage_log.append(&record) {
+            warn!("Failed to record usage: {:#}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for OpenAiCompatibleBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn transcribe(
+        &self,
+        audio: &AudioInput,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript, TranscribeError> {
+        let url = self.endpoint(match options.mode {
+            TranscriptionMode::Transcribe => "audio/transcriptions",
+            TranscriptionMode::Translate => "audio/translations",
+        });
+        let encoded = self.encode(audio).await?;
+        debug!(
+            "Sending {} file ({} bytes) to {}...",
+            self.upload_codec.mime_type(),
+            encoded.len(),
+            url
+        );
+        let started = Instant::now();
+        let result = self.upload(&url, &encoded, options).await;
+        self.log_usage(audio, encoded.len(), started, &result);
+        result
+    }
+}
+
+/// Passes successful responses through and turns the others into a `TranscribeError`.
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, TranscribeError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = retry_after(&response);
+    let error_text = response.text().await.unwrap_or_default();
+    error!("Error response ({}): {}", status, error_text);
+    Err(TranscribeError::from_status(
+        status,
+        retry_after,
+        &error_text,
+    ))
+}
@@ -0,0 +1,106 @@
+use log::{debug, warn};
+
+/// Whisper only looks at the last 224 tokens of a prompt, and some
+/// OpenAI-compatible servers reject longer prompts outright.
+pub const PROMPT_TOKEN_LIMIT: usize = 224;
+
+/// Rough token count for a single word. Without the model's tokenizer we
+/// assume about three characters per token, which overestimates for common
+/// English words and stays safe for names and acronyms.
+fn estimate_word_tokens(word: &str) -> usize {
+    word.chars().count().div_ceil(3).max(1)
+}
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.split_whitespace().map(estimate_word_tokens).sum()
+}
+
+/// Cuts `text` at a word boundary so that it fits in `limit` tokens.
+pub fn truncate_to_tokens(text: &str, limit: usize) -> String {
+    let mut used = 0;
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        let cost = estimate_word_tokens(word);
+        if used + cost > limit {
+            break;
+        }
+        used += cost;
+        words.push(word);
+    }
+    words.join(" ")
+}
+
+/// Combines the glossary and free-text prompt into the `prompt` field sent to
+/// the provider. Glossary terms come first so they survive truncation.
+pub fn build_prompt(glossary: &[String], prompt: &str) -> Option<String> {
+    let terms: Vec<&str> = glossary
+        .iter()
+        .map(|term| term.trim())
+        .filter(|term| !term.is_empty())
+        .collect();
+
+    let mut parts = Vec::new();
+    if !terms.is_empty() {
+        parts.push(format!("{}.", terms.join(", ")));
+    }
+    if !prompt.trim().is_empty() {
+        parts.push(prompt.trim().to_string());
+    }
+    if parts.is_empty() {
+        return None;
+    }
+
+    let combined = parts.join(" ");
+    let tokens = estimate_tokens(&combined);
+    if tokens <= PROMPT_TOKEN_LIMIT {
+        debug!("Prompt uses about {} tokens", tokens);
+        return Some(combined);
+    }
+
+    warn!(
+        "Prompt is about {} tokens, truncating to {}",
+        tokens, PROMPT_TOKEN_LIMIT
+    );
+    Some(truncate_to_tokens(&combined, PROMPT_TOKEN_LIMIT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glossary(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|term| term.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_settings_send_no_prompt() {
+        assert_eq!(build_prompt(&glossary(&[" ", ""]), "  "), None);
+    }
+
+    #[test]
+    fn glossary_comes_before_the_prompt() {
+        assert_eq!(
+            build_prompt(
+                &glossary(&["Kubernetes", " gRPC ", ""]),
+                " A talk about servers. "
+            ),
+            Some("Kubernetes, gRPC. A talk about servers.".to_string())
+        );
+        assert_eq!(build_prompt(&[], "Casual."), Some("Casual.".to_string()));
+    }
+
+    #[test]
+    fn long_prompts_are_truncated_at_a_word() {
+        let prompt = "word ".repeat(PROMPT_TOKEN_LIMIT);
+        let built = build_prompt(&glossary(&["Glossary"]), &prompt).unwrap();
+        assert!(built.starts_with("Glossary. word"));
+        assert!(!built.ends_with(' '));
+        assert!(estimate_tokens(&built) <= PROMPT_TOKEN_LIMIT);
+    }
+
+    #[test]
+    fn estimates_tokens_per_word() {
+        assert_eq!(estimate_tokens("a abc abcd  "), 4);
+        assert_eq!(truncate_to_tokens("abcdef ghi jkl", 3), "abcdef ghi");
+    }
+}
@@ -0,0 +1,113 @@
+use super::{StatusSender, TranscribeError, TranscriptionStatus};
+use crate::config::Config;
+use log::{debug, warn};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+/// How often and how patiently failed API calls are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+        }
+    }
+
+    /// Exponential backoff: the base delay doubled for every previous attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Longest `Retry-After` we wait out; a server asking for more is not waited for
+/// longer than this, so the user is never left staring at a countdown.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Parses a `Retry-After` header given in seconds. The HTTP-date form is not
+/// used by the providers we talk to and falls back to regular backoff.
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    parse_retry_after(value.to_str().ok()?)
+}
+
+/// `Retry-After` is a whole number of seconds (RFC 9110, section 10.2.3).
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let seconds: u64 = value.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Sends the request built by `build`, retrying connection failures and 5xx
+/// responses with exponential backoff, and waiting out HTTP 429 as long as
+/// the server asks (up to a minute). `build` is called once per attempt
+/// because multipart bodies cannot be cloned.
+///
+/// A request that times out after connecting is not retried: it has already
+/// used up the whole request timeout, and an upload would start over.
+///
+/// Responses with other error statuses, or the last one once retries are
+/// exhausted, are returned as-is for the caller to report.
+pub async fn send_with_retry(
+    policy: &RetryPolicy,
+    status: Option<&StatusSender>,
+    mut build: impl FnMut() -> Result<RequestBuilder, TranscribeError>,
+) -> Result<Response, TranscribeError> {
+    let mut attempt = 0;
+    loop {
+        let result = build()?.send().await;
+        let (delay, update) = match &result {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                let wait = retry_after(response).unwrap_or_else(|| policy.backoff(attempt));
+                (wait, TranscriptionStatus::RateLimited { wait })
+            }
+            Ok(response) if response.status().is_server_error() => {
+                let delay = policy.backoff(attempt);
+                warn!("Server responded with {}", response.status());
+                (delay, retrying(attempt, policy, delay))
+            }
+            // Includes connect timeouts
+            Err(e) if e.is_connect() => {
+                let delay = policy.backoff(attempt);
+                warn!("Request failed: {}", e);
+                (delay, retrying(attempt, policy, delay))
+            }
+            _ => return Ok(result?),
+        };
+
+        if attempt >= policy.max_retries {
+            warn!("Giving up after {} retries", attempt);
+            return Ok(result?);
+        }
+
+        debug!("Retrying in {:?} ({:?})", delay, update);
+        if let Some(status) = status {
+            // Nobody listening is fine, the retry happens either way
+            let _ = status.send(update);
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+fn retrying(attempt: u32, policy: &RetryPolicy, delay: Duration) -> TranscriptionStatus {
+    TranscriptionStatus::Retrying {
+        attempt: attempt + 1,
+        max_retries: policy.max_retries,
+        delay,
+    }
+}
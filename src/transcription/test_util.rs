@@ -0,0 +1,34 @@
+//! Fixtures shared by the transcription tests.
+
+use super::{AudioInput, Segment};
+
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// Mono samples made of `(level, secs)` stretches, alternating around zero at each level.
+pub fn samples(stretches: &[(f32, f64)]) -> Vec<f32> {
+    stretches
+        .iter()
+        .flat_map(|&(level, secs)| {
+            let frames = (secs * SAMPLE_RATE as f64).round() as usize;
+            (0..frames).map(move |i| if i % 2 == 0 { level } else { -level })
+        })
+        .collect()
+}
+
+/// A mono recording of `(level, secs)` stretches.
+pub fn recording(stretches: &[(f32, f64)]) -> AudioInput {
+    AudioInput {
+        samples: samples(stretches),
+        sample_rate: SAMPLE_RATE,
+        channels: 1,
+    }
+}
+
+pub fn segment(start: f64, end: f64, text: &str) -> Segment {
+    Segment {
+        start,
+        end,
+        text: text.to_string(),
+        ..Segment::default()
+    }
+}
@@ -0,0 +1,204 @@
+use super::hallucination::HallucinationReason;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A stretch of speech with its position in the recording, in seconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    /// Average log probability of the segment's tokens; closer to 0 is more confident
+    #[serde(default)]
+    pub avg_logprob: Option<f64>,
+    /// Probability that the segment contains no speech at all
+    #[serde(default)]
+    pub no_speech_prob: Option<f64>,
+    /// Set when the segment looks made up rather than heard
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hallucination: Option<HallucinationReason>,
+}
+
+/// The result of a transcription, independent of the provider that produced it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub text: String,
+    /// Language the provider detected or was told to use, if it reported one
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Length of the transcribed audio in seconds, if the provider reported it
+    #[serde(default)]
+    pub duration: Option<f64>,
+    /// Timed segments; empty when the provider only returned plain text
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    /// Name of the provider that produced the transcript
+    #[serde(default)]
+    pub provider: Option<String>,
+}
+
+impl Segment {
+    /// Whether the provider was unsure about the words or whether there was speech at all.
+    pub fn is_low_confidence(&self, min_avg_logprob: f64, max_no_speech_prob: f64) -> bool {
+        self.hallucination.is_some()
+            || self
+                .avg_logprob
+                .is_some_and(|logprob| logprob < min_avg_logprob)
+            || self
+                .no_speech_prob
+                .is_some_and(|probability| probability > max_no_speech_prob)
+    }
+}
+
+/// Segment words that may be missing from the text before the next one is
+/// looked for, e.g. fillers dropped by post-processing.
+const MAX_SKIPPED_WORDS: usize = 3;
+
+impl Transcript {
+    /// Byte ranges of `text` that belong to low-confidence segments. The text
+    /// may have been post-processed, so segments are aligned word by word and
+    /// skipped when too few of their words are found.
+    pub fn low_confidence_spans(
+        &self,
+        min_avg_logprob: f64,
+        max_no_speech_prob: f64,
+    ) -> Vec<Range<usize>> {
+        let words = words(&self.text);
+        let mut cursor = 0;
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        for segment in &self.segments {
+            let segment_words: Vec<String> = words_of(&segment.text);
+            let mut matched: Vec<usize> = Vec::new();
+            let mut position = cursor;
+            for word in &segment_words {
+                let found = words[position.min(words.len())..]
+                    .iter()
+                    .take(MAX_SKIPPED_WORDS + 1)
+                    .position(|(_, candidate)| candidate == word);
+                if let Some(offset) = found {
+                    matched.push(position + offset);
+                    position += offset + 1;
+                }
+            }
+            if matched.is_empty() || matched.len() * 2 < segment_words.len() {
+                continue;
+            }
+            cursor = position;
+            if segment.is_low_confidence(min_avg_logprob, max_no_speech_prob) {
+                let start = words[matched[0]].0.start;
+                let end = words[matched[matched.len() - 1]].0.end;
+                match spans.last_mut() {
+                    // Neighbouring flagged segments form one span
+                    Some(last) if self.text[last.end..start].trim().is_empty() => last.end = end,
+                    _ => spans.push(start..end),
+                }
+            }
+        }
+        spans
+    }
+}
+
+/// Lowercased words of `text` with their byte ranges, ignoring punctuation.
+fn words(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric() || c == '\'', start) {
+            (true, None) => start = Some(index),
+            (false, Some(begin)) => {
+                words.push((begin..index, text[begin..index].to_lowercase()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+pub(super) fn words_of(text: &str) -> Vec<String> {
+    words(text).into_iter().map(|(_, word)| word).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, avg_logprob: f64) -> Segment {
+        Segment {
+            text: text.to_string(),
+            avg_logprob: Some(avg_logprob),
+            ..Default::default()
+        }
+    }
+
+    fn spans(transcript: &Transcript) -> Vec<&str> {
+        transcript
+            .low_confidence_spans(-1.0, 0.6)
+            .into_iter()
+            .map(|span| &transcript.text[span])
+            .collect()
+    }
+
+    #[test]
+    fn flags_unsure_segments() {
+        let transcript = Transcript {
+            text: "Hello there. Mumbled words. Goodbye.".to_string(),
+            segments: vec![
+                segment(" Hello there.", -0.2),
+                segment(" Mumbled words.", -1.5),
+                segment(" Goodbye.", -0.3),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(spans(&transcript), ["Mumbled words"]);
+    }
+
+    #[test]
+    fn neighbouring_segments_form_one_span() {
+        let transcript = Transcript {
+            text: "one two three".to_string(),
+            segments: vec![
+                segment("one", -0.1),
+                segment("two", -2.0),
+                segment("three", -2.0),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(spans(&transcript), ["two three"]);
+    }
+
+    #[test]
+    fn aligns_with_post_processed_text() {
+        let transcript = Transcript {
+            text: "So we meet at two. Maybe Tuesday.".to_string(),
+            segments: vec![
+                segment("so, um, we meet at two", -0.1),
+                segment("maybe, uh, tuesday", -1.5),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(spans(&transcript), ["Maybe Tuesday"]);
+    }
+
+    #[test]
+    fn skips_segments_missing_from_the_text() {
+        let mut hallucinated = segment("thanks for watching", -0.1);
+        hallucinated.hallucination = Some(HallucinationReason::PhantomPhrase);
+        let transcript = Transcript {
+            text: "Real words here.".to_string(),
+            segments: vec![segment("real words here", -0.1), hallucinated],
+            ..Default::default()
+        };
+        assert!(spans(&transcript).is_empty());
+    }
+
+    #[test]
+    fn no_speech_probability_counts_as_low_confidence() {
+        let segment = Segment {
+            no_speech_prob: Some(0.9),
+            ..Default::default()
+        };
+        assert!(segment.is_low_confidence(-1.0, 0.6));
+        assert!(!Segment::default().is_low_confidence(-1.0, 0.6));
+    }
+}
@@ -194,9 +194,9 @@ fn build_max_duration_label(state_manager: &Arc<StateManager>) -> gtk::Label {
     let state_manager_clone = Arc::clone(state_manager);
     glib::MainContext::default().spawn_local(async move {
         let config = state_manager_clone.get_config();
-        let max_duration = config.max_recording_duration();
+        let max_duration = config.max_chunk_secs();
         label_clone.set_text(&format!(
-            "Maximum recording duration: {:.2} seconds",
+            "Maximum chunk duration: {:.2} seconds",
             max_duration
         ));
     });
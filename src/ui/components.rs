@@ -197,9 +197,9 @@ impl MainWindowBuilder {
         let state_manager = self.state_manager.clone();
         glib::MainContext::default().spawn_local(async move {
             let config = state_manager.get_config();
-            let max_duration = config.max_recording_duration();
+            let max_duration = config.max_chunk_secs();
             label_clone.set_text(&format!(
-                "Maximum recording duration: {:.2} seconds",
+                "Maximum chunk duration: {:.2} seconds",
                 max_duration
             ));
         });
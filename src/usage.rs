@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One request that sent audio to a provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// Unix time the request was sent
+    pub timestamp: i64,
+    pub provider: String,
+    pub model: String,
+    /// Length of the uploaded audio in seconds
+    pub audio_secs: f64,
+    /// Size of the uploaded file
+    pub bytes: usize,
+    pub latency_ms: u64,
+    /// Why the request failed; `None` when it succeeded
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl UsageRecord {
+    pub fn now_timestamp() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default()
+    }
+}
+
+/// Append-only log of provider requests, one JSON object per line.
+#[derive(Debug, Clone)]
+pub struct UsageLog {
+    path: PathBuf,
+}
+
+impl Default for UsageLog {
+    fn default() -> Self {
+        Self::new(
+            glib::user_data_dir()
+                .join("voice_transcriber")
+                .join("usage.jsonl"),
+        )
+    }
+}
+
+impl UsageLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn append(&self, record: &UsageRecord) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create data directory {:?}", dir))?;
+        }
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        // A single write per record keeps lines from concurrent chunk uploads intact
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write usage log {:?}", self.path))?;
+        debug!(
+            "Logged {:.1}s of audio sent to {}",
+            record.audio_secs, record.provider
+        );
+        Ok(())
+    }
+
+    pub fn load(&self) -> Result<Vec<UsageRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read usage log {:?}", self.path))?;
+        Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    warn!("Skipping unreadable usage record: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    pub fn stats(&self) -> Result<UsageStats> {
+        Ok(UsageStats::from_records(&self.load()?))
+    }
+}
+
+/// Sums over a period of usage records.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageTotals {
+    pub requests: usize,
+    pub failed: usize,
+    /// Audio of successful requests, the billable amount quotas apply to
+    pub audio_secs: f64,
+    /// Audio of failed requests, which providers do not bill
+    pub failed_audio_secs: f64,
+    pub bytes: usize,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        if record.error.is_some() {
+            self.failed += 1;
+            self.failed_audio_secs += record.audio_secs;
+            return;
+        }
+        self.audio_secs += record.audio_secs;
+        self.bytes += record.bytes;
+    }
+
+    pub fn audio_minutes(&self) -> f64 {
+        self.audio_secs / 60.0
+    }
+}
+
+/// Usage per local calendar day ("2024-05-31") and month ("2024-05").
+#[derive(Debug, Clone, Default)]
+pub struct UsageStats {
+    pub daily: BTreeMap<String, UsageTotals>,
+    pub monthly: BTreeMap<String, UsageTotals>,
+}
+
+impl UsageStats {
+    pub fn from_records(records: &[UsageRecord]) -> Self {
+        let mut stats = Self::default();
+        for record in records {
+            stats
+                .daily
+                .entry(local_date(record.timestamp, "%Y-%m-%d"))
+                .or_default()
+                .add(record);
+            stats
+                .monthly
+                .entry(local_date(record.timestamp, "%Y-%m"))
+                .or_default()
+                .add(record);
+        }
+        stats
+    }
+
+    pub fn today(&self) -> UsageTotals {
+        let today = local_date(UsageRecord::now_timestamp(), "%Y-%m-%d");
+        self.daily.get(&today).cloned().unwrap_or_default()
+    }
+
+    pub fn this_month(&self) -> UsageTotals {
+        let month = local_date(UsageRecord::now_timestamp(), "%Y-%m");
+        self.monthly.get(&month).cloned().unwrap_or_default()
+    }
+}
+
+fn local_date(timestamp: i64, format: &str) -> String {
+    glib::DateTime::from_unix_local(timestamp)
+        .and_then(|date| date.format(format))
+        .map(|date| date.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-06-15 15:06:40 UTC, mid-month in every time zone
+    const TIMESTAMP: i64 = 1_750_000_000;
+
+    fn record(audio_secs: f64, error: Option<&str>) -> UsageRecord {
+        UsageRecord {
+            timestamp: TIMESTAMP,
+            provider: "Groq".to_string(),
+            model: "whisper-large-v3".to_string(),
+            audio_secs,
+            bytes: 1000,
+            latency_ms: 500,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn failed_requests_are_not_billed() {
+        let records = [
+            record(60.0, None),
+            record(120.0, Some("HTTP 503")),
+            record(30.0, None),
+        ];
+        let stats = UsageStats::from_records(&records);
+        let month = &stats.monthly["2025-06"];
+        assert_eq!(month.requests, 3);
+        assert_eq!(month.failed, 1);
+        assert_eq!(month.audio_minutes(), 1.5);
+        assert_eq!(month.failed_audio_secs, 120.0);
+        assert_eq!(month.bytes, 2000);
+    }
+}